@@ -1,17 +1,20 @@
-use std::{error::Error, io};
+use std::{error::Error, io, time::Duration};
 
 use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
+    event::{DisableMouseCapture, EnableMouseCapture, Event, MouseButton, MouseEvent, MouseEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::prelude::*;
-use tui_textarea::{Input, Key, TextArea};
+use tui_textarea::{CursorMove, Input, Key, TextArea};
 
 mod models;
 use models::{
-    app::{App, Filter, InputMode},
+    app::{App, InputMode, SortKey},
     config::AppConfig,
+    export::to_text,
+    keymap::{push_input, Action, Context, Keymap, Lookup},
+    task::Task,
 };
 use models::{
     app::{InputArea, PageLayout},
@@ -19,8 +22,59 @@ use models::{
     ui::ui,
 };
 
+const USAGE: &str = "usage: rust-todo [list [QUERY] | --filter QUERY | --help]
+
+  list [QUERY]      print the tasks matching QUERY and exit
+  --filter QUERY    start with QUERY applied as the filter
+
+QUERY example: status:new,hold topic:backend tag:urgent due<7d text:\"login\"
+terms are combined with AND (implied), OR, NOT or a leading '-'";
+
 fn main() -> Result<(), Box<dyn Error>> {
     let conf = AppConfig::load_config();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    for field in conf.custom_fields.iter() {
+        if let Err(e) = field.check() {
+            eprintln!("invalid config: {}", e);
+            std::process::exit(2);
+        }
+    }
+    let mut app = App::new(&conf);
+    match Keymap::from_config(&conf.keys) {
+        Ok(keymap) => app.keymap = keymap,
+        Err(errors) => {
+            for e in errors {
+                eprintln!("invalid key binding: {}", e);
+            }
+            std::process::exit(2);
+        }
+    }
+    match args.first().map(String::as_str) {
+        None => (),
+        Some("list") => {
+            if let Err(e) = app.set_query(&args[1..].join(" ")) {
+                eprintln!("invalid query: {}", e);
+                std::process::exit(2);
+            }
+            let tasks: Vec<Task> = app.items.iter().filter(|t| t.display).cloned().collect();
+            print!("{}", to_text(&tasks));
+            return Ok(());
+        }
+        Some("--filter" | "-f") => {
+            if let Err(e) = app.set_query(&args[1..].join(" ")) {
+                eprintln!("invalid query: {}", e);
+                std::process::exit(2);
+            }
+        }
+        Some("--help" | "-h") => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        Some(other) => {
+            eprintln!("unknown argument '{}'\n{}", other, USAGE);
+            std::process::exit(2);
+        }
+    }
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
@@ -38,9 +92,10 @@ fn main() -> Result<(), Box<dyn Error>> {
         TextArea::default(),
         TextArea::default(),
         TextArea::default(),
+        TextArea::default(),
+        TextArea::default(),
     ];
 
-    let app = App::new();
     let res = run_app(&mut terminal, app, text_areas, conf, formatter);
 
     disable_raw_mode()?;
@@ -61,188 +116,494 @@ fn main() -> Result<(), Box<dyn Error>> {
 fn run_app<B: Backend>(
     terminal: &mut Terminal<B>,
     mut app: App,
-    mut text_areas: [TextArea; 4],
+    mut text_areas: [TextArea; 6],
     conf: AppConfig,
     formatter: Formatter,
 ) -> io::Result<()> {
     loop {
         terminal.draw(|f| ui(f, &mut app, &mut text_areas, &conf, &formatter))?;
+        // tick on every iteration, steady input (typing, mouse motion) would
+        // otherwise keep the poll from ever timing out
+        let ready = crossterm::event::poll(Duration::from_secs(1))?;
+        app.tick();
+        if !ready {
+            continue;
+        }
+        let event = crossterm::event::read()?;
+        if let Event::Mouse(mouse) = event {
+            handle_mouse(&mut app, mouse);
+            continue;
+        }
         match app.input_mode {
-            models::app::InputMode::Normal => match crossterm::event::read()?.into() {
-                Input {
-                    key: Key::Char('q'),
-                    ..
-                } => return Ok(()),
-                Input {
-                    key: Key::Char('h'),
-                    ..
-                } => app.layout = PageLayout::Horizontal,
-                Input {
-                    key: Key::Char('v'),
-                    ..
-                } => app.layout = PageLayout::Vertical,
-                Input {
-                    key: Key::Char('n'),
-                    ..
-                } => app.input_mode = InputMode::Editing,
-                Input {
-                    key: Key::Char('s'),
-                    ..
-                } => {
-                    app.input_mode = InputMode::SubTask;
-                    app.input_area = InputArea::Task;
-                }
-                Input {
-                    key: Key::Char('c'),
-                    ..
-                } => app.change_status(),
-                Input {
-                    key: Key::Char('a'),
-                    ..
-                } => {
-                    app.input_mode = InputMode::Comment;
-                    app.input_area = InputArea::Comment;
+            models::app::InputMode::Normal => {
+                let mut contexts = Vec::new();
+                if !app.search_query.is_empty() {
+                    contexts.push(Context::Search);
                 }
-                Input {
-                    key: Key::Char('A'),
-                    shift: true,
-                    ..
-                } => app.delete_comment(),
-                Input {
-                    key: Key::Char('D'),
-                    ..
-                } => app.delete(),
-                Input {
-                    key: Key::Char('E'),
-                    shift: true,
-                    ..
-                } => match app.edit_last_comment() {
-                    Some(i) => {
-                        text_areas[3] = TextArea::from(i.lines());
-                        app.input_mode = InputMode::CommentEdit;
+                match app.layout {
+                    PageLayout::Board => contexts.push(Context::Board),
+                    PageLayout::Calendar => contexts.push(Context::Calendar),
+                    _ => (),
+                }
+                contexts.push(Context::Normal);
+                let action = match resolve(&mut app, &contexts, &event.into()) {
+                    Some(action) => action,
+                    None => continue,
+                };
+                match action {
+                    Action::Quit => return Ok(()),
+                    Action::ColumnLeft => app.board_column_step(false),
+                    Action::ColumnRight => app.board_column_step(true),
+                    Action::CardDown => app.board_card_step(true),
+                    Action::CardUp => app.board_card_step(false),
+                    Action::MoveCardLeft => app.move_card(false),
+                    Action::MoveCardRight => app.move_card(true),
+                    Action::PreviousDay => app.calendar_step(-1),
+                    Action::NextDay => app.calendar_step(1),
+                    Action::PreviousWeek => app.calendar_step(-7),
+                    Action::NextWeek => app.calendar_step(7),
+                    Action::Today => app.calendar_today(),
+                    Action::Board => app.toggle_board(),
+                    Action::Calendar => app.toggle_calendar(),
+                    Action::Stats => app.input_mode = InputMode::Stats,
+                    Action::HorizontalLayout => app.set_layout(PageLayout::Horizontal),
+                    Action::VerticalLayout => app.set_layout(PageLayout::Vertical),
+                    Action::NextHit => app.next_hit(),
+                    Action::PreviousHit => app.previous_hit(),
+                    Action::NewTask => {
+                        text_areas[5] = TextArea::from(app.fields_text(None).lines());
+                        app.input_mode = InputMode::Editing;
+                    }
+                    Action::NewSubTask => {
+                        text_areas[5] = TextArea::from(app.fields_text(None).lines());
+                        app.input_mode = InputMode::SubTask;
+                        app.input_area = InputArea::Task;
+                    }
+                    Action::ChangeStatus => app.change_status(),
+                    Action::AddComment => {
+                        app.input_mode = InputMode::Comment;
                         app.input_area = InputArea::Comment;
                     }
-                    None => (),
-                },
-                Input {
-                    key: Key::Char('e'),
-                    ..
-                } => match app.edit() {
-                    Some(i) => {
-                        if i.3 {
-                            app.input_mode = InputMode::SubTaskModify;
-                            app.input_area = InputArea::Task;
-                        } else {
-                            app.input_mode = InputMode::Modify;
+                    Action::DeleteLastComment => app.delete_comment(),
+                    Action::DeleteTask => app.delete(),
+                    Action::EditLastComment => {
+                        if let Some(i) = app.edit_last_comment() {
+                            text_areas[3] = TextArea::from(i.lines());
+                            app.input_mode = InputMode::CommentEdit;
+                            app.input_area = InputArea::Comment;
+                        }
+                    }
+                    Action::EditTask => {
+                        if let Some(i) = app.edit() {
+                            if i.3 {
+                                app.input_mode = InputMode::SubTaskModify;
+                                app.input_area = InputArea::Task;
+                            } else {
+                                app.input_mode = InputMode::Modify;
+                            }
+                            text_areas[0] = TextArea::from(i.0.lines());
+                            text_areas[1] = TextArea::from(i.1.lines());
+                            text_areas[2] = TextArea::from(i.2.lines());
+                            text_areas[5] = TextArea::from(i.4.lines());
+                        }
+                    }
+                    Action::Next => app.next(),
+                    Action::Previous => app.previous(),
+                    Action::Help => {
+                        app.help_scroll = 0;
+                        app.input_mode = InputMode::Help;
+                    }
+                    Action::ScrollDown => app.scroll_down(),
+                    Action::ScrollUp => app.scroll_up(),
+                    Action::Filter => app.input_mode = InputMode::FilterMode,
+                    Action::FocusDetails => app.focus_details(),
+                    Action::AddLink => app.open_prompt(InputMode::Link),
+                    Action::Templates => app.open_templates(),
+                    Action::Sort => app.input_mode = InputMode::Sort,
+                    Action::Export => app.open_prompt(InputMode::Export),
+                    Action::Duplicate => app.duplicate(false),
+                    Action::DuplicateTree => app.duplicate(true),
+                    Action::Assign => {
+                        if let Some(i) = app.assignee_value() {
+                            text_areas[4] = TextArea::from(i.lines());
+                            app.open_prompt(InputMode::Assign);
                         }
-                        text_areas[0] = TextArea::from(i.0.lines());
-                        text_areas[1] = TextArea::from(i.1.lines());
-                        text_areas[2] = TextArea::from(i.2.lines());
-                    }
-                    None => (),
-                },
-                Input {
-                    key: Key::Down | Key::Char('j'),
-                    ..
-                } => app.next(),
-                Input {
-                    key: Key::Up | Key::Char('k'),
-                    ..
-                } => app.previous(),
-                Input {
-                    key: Key::Char('?'),
-                    ..
-                } => {
-                    app.input_mode = InputMode::Help;
-                }
-                Input {
-                    key: Key::Char(','),
-                    ..
-                } => app.scroll_down(),
-                Input {
-                    key: Key::Char('.'),
-                    ..
-                } => app.scroll_up(),
-                Input {
-                    key: Key::Char('f'),
-                    ..
-                } => app.input_mode = InputMode::FilterMode,
+                    }
+                    Action::Defer => {
+                        if let Some(i) = app.defer_value() {
+                            text_areas[4] = TextArea::from(i.lines());
+                            app.open_prompt(InputMode::Defer);
+                        }
+                    }
+                    Action::Mark => app.toggle_mark(),
+                    Action::Visual => app.start_visual(),
+                    Action::Bulk => app.open_bulk(),
+                    Action::Undo => app.undo(),
+                    Action::ArchiveDone => app.archive_done(),
+                    Action::Archive => app.open_archive(),
+                    Action::Search => {
+                        text_areas[4] = TextArea::from(app.search_query.lines());
+                        text_areas[4].move_cursor(CursorMove::End);
+                        app.input_mode = InputMode::Search;
+                    }
+                    Action::GoTo => {
+                        text_areas[4] = TextArea::default();
+                        app.open_goto();
+                    }
+                    Action::Command => {
+                        text_areas[4] = TextArea::default();
+                        app.open_command();
+                    }
+                    Action::Views => app.open_views(),
+                    Action::ClearView => app.clear_view(),
+                    Action::View(i) => app.apply_view(i),
+                    Action::ToggleGrouping => app.toggle_grouping(),
+                    Action::ToggleGroup => app.toggle_group(),
+                    Action::NextGroup => app.jump_group(true),
+                    Action::PreviousGroup => app.jump_group(false),
+                    Action::Clear => {
+                        app.clear_marks();
+                        app.clear_search();
+                    }
+                    _ => {}
+                }
+            }
+            models::app::InputMode::Sort => match resolve(&mut app, &[Context::Sort], &event.into()) {
+                Some(Action::Close) => app.input_mode = InputMode::Normal,
+                Some(Action::SortBy(i, descending)) => {
+                    if let Some(key) = SortKey::iterator().nth(i) {
+                        app.set_sort(Some((key, descending)));
+                    }
+                }
+                Some(Action::Field) if !app.custom_fields.is_empty() => app.open_prompt(InputMode::FieldSort),
+                Some(Action::Clear) => app.set_sort(None),
+                _ => {}
+            },
+            models::app::InputMode::ViewPicker => match resolve(&mut app, &[Context::Views], &event.into()) {
+                Some(Action::Close) => app.input_mode = InputMode::Normal,
+                Some(Action::Next) => app.view_next(),
+                Some(Action::Previous) => app.view_previous(),
+                Some(Action::View(i)) => app.apply_view(i),
+                Some(Action::Select) => {
+                    if let Some(i) = app.view_state.selected() {
+                        app.apply_view(i);
+                    }
+                }
                 _ => {}
             },
-            models::app::InputMode::FilterMode => match crossterm::event::read()?.into() {
-                Input { key: Key::Esc, .. } => {
+            models::app::InputMode::Command => {
+                let input: Input = event.into();
+                match resolve(&mut app, &[Context::Command, Context::Prompt], &input) {
+                    Some(Action::Close) => {
+                        text_areas[4] = TextArea::default();
+                        app.close_prompt();
+                    }
+                    Some(Action::Submit) => {
+                        app.run_command(&text_areas[4].lines().concat());
+                        if !matches!(app.input_mode, InputMode::Command) {
+                            text_areas[4] = TextArea::default();
+                        }
+                    }
+                    Some(Action::Complete) => {
+                        let line = app.complete_command(&text_areas[4].lines().concat());
+                        text_areas[4] = TextArea::from(line.lines());
+                        text_areas[4].move_cursor(CursorMove::End);
+                    }
+                    Some(action @ (Action::HistoryBack | Action::HistoryForward)) => {
+                        if let Some(line) = app.history_step(action == Action::HistoryBack) {
+                            text_areas[4] = TextArea::from(line.lines());
+                            text_areas[4].move_cursor(CursorMove::End);
+                        }
+                    }
+                    _ if app.pending_keys.is_empty() => {
+                        app.completions.clear();
+                        text_areas[4].input(input);
+                    }
+                    _ => {}
+                }
+            }
+            models::app::InputMode::Search => {
+                let input: Input = event.into();
+                match resolve(&mut app, &[Context::Prompt], &input) {
+                    Some(Action::Close) => {
+                        text_areas[4] = TextArea::default();
+                        app.clear_search();
+                        app.input_mode = InputMode::Normal;
+                    }
+                    Some(Action::Submit) => {
+                        text_areas[4] = TextArea::default();
+                        app.input_mode = InputMode::Normal;
+                    }
+                    _ if app.pending_keys.is_empty() => {
+                        text_areas[4].input(input);
+                        app.search(text_areas[4].lines().concat());
+                    }
+                    _ => {}
+                }
+            }
+            models::app::InputMode::Stats => {
+                if let Some(Action::Close) = resolve(&mut app, &[Context::Stats], &event.into()) {
                     app.input_mode = InputMode::Normal;
                 }
-                Input {
-                    key: Key::Char('n'),
-                    ..
-                } => {
-                    app.filter_items(Filter::New);
-                }
-                Input {
-                    key: Key::Char('h'),
-                    ..
-                } => {
-                    app.filter_items(Filter::Hold);
-                }
-                Input {
-                    key: Key::Char('i'),
-                    ..
-                } => {
-                    app.filter_items(Filter::InProgress);
-                }
-                Input {
-                    key: Key::Char('d'),
-                    ..
-                } => {
-                    app.filter_items(Filter::Done);
-                }
-                Input {
-                    key: Key::Char('o'),
-                    ..
-                } => {
-                    app.filter_items(Filter::NotDone);
-                }
-                Input {
-                    key: Key::Char('a'),
-                    ..
-                } => {
-                    app.filter_items(Filter::All);
+            }
+            models::app::InputMode::Help => match resolve(&mut app, &[Context::Help], &event.into()) {
+                Some(Action::Close) => app.input_mode = InputMode::Normal,
+                Some(Action::ScrollDown) => app.help_scroll = app.help_scroll.saturating_add(1),
+                Some(Action::ScrollUp) => app.help_scroll = app.help_scroll.saturating_sub(1),
+                _ => {}
+            },
+            models::app::InputMode::GoTo => {
+                let input: Input = event.into();
+                match resolve(&mut app, &[Context::GoTo, Context::Prompt], &input) {
+                    Some(Action::Close) => {
+                        text_areas[4] = TextArea::default();
+                        app.input_mode = InputMode::Normal;
+                    }
+                    Some(Action::Submit) => {
+                        text_areas[4] = TextArea::default();
+                        app.goto();
+                    }
+                    Some(Action::Next) => app.goto_next(),
+                    Some(Action::Previous) => app.goto_previous(),
+                    _ if app.pending_keys.is_empty() => {
+                        text_areas[4].input(input);
+                        app.goto_search(&text_areas[4].lines().concat());
+                    }
+                    _ => {}
+                }
+            }
+            models::app::InputMode::Archive => match resolve(&mut app, &[Context::Archive], &event.into()) {
+                Some(Action::Close) => app.input_mode = InputMode::Normal,
+                Some(Action::Next) => app.archive_next(),
+                Some(Action::Previous) => app.archive_previous(),
+                Some(Action::Restore) => app.restore(),
+                Some(Action::Search) => {
+                    text_areas[4] = TextArea::from(app.archive_query.lines());
+                    text_areas[4].move_cursor(CursorMove::End);
+                    app.input_mode = InputMode::ArchiveSearch;
                 }
                 _ => {}
             },
-            _ => match crossterm::event::read()?.into() {
-                Input { key: Key::Esc, .. } => {
-                    text_areas[0] = TextArea::default();
-                    text_areas[1] = TextArea::default();
-                    text_areas[2] = TextArea::default();
-                    text_areas[3] = TextArea::default();
-                    app.input_mode = InputMode::Normal;
-                    app.input_area = InputArea::Topic;
-                }
-                Input {
-                    key: Key::Char('s'),
-                    ctrl: true,
-                    ..
-                } => {
-                    app.create(
-                        &text_areas[0],
-                        &text_areas[1],
-                        &text_areas[2],
-                        &text_areas[3],
-                    );
-                    text_areas[0] = TextArea::default();
-                    text_areas[1] = TextArea::default();
-                    text_areas[2] = TextArea::default();
-                    text_areas[3] = TextArea::default();
-                }
-                Input { key: Key::Tab, .. } => {
-                    app.change_input_area();
-                }
-                input => {
-                    text_areas[app.input_area.index()].input(input);
+            models::app::InputMode::ArchiveSearch => {
+                let input: Input = event.into();
+                match resolve(&mut app, &[Context::Prompt], &input) {
+                    Some(Action::Close) => {
+                        text_areas[4] = TextArea::default();
+                        app.search_archive(String::new());
+                        app.input_mode = InputMode::Archive;
+                    }
+                    Some(Action::Submit) => {
+                        text_areas[4] = TextArea::default();
+                        app.input_mode = InputMode::Archive;
+                    }
+                    _ if app.pending_keys.is_empty() => {
+                        text_areas[4].input(input);
+                        app.search_archive(text_areas[4].lines().concat());
+                    }
+                    _ => {}
+                }
+            }
+            models::app::InputMode::Visual => match resolve(&mut app, &[Context::Visual], &event.into()) {
+                Some(Action::Close) => app.stop_visual(),
+                Some(Action::Next) => {
+                    app.next();
+                    app.mark_range();
+                }
+                Some(Action::Previous) => {
+                    app.previous();
+                    app.mark_range();
+                }
+                Some(Action::Bulk) => app.open_bulk(),
+                _ => {}
+            },
+            models::app::InputMode::Bulk => match resolve(&mut app, &[Context::Bulk], &event.into()) {
+                Some(Action::Close) => app.input_mode = InputMode::Normal,
+                Some(Action::SetStatus(status)) => app.bulk_status(status),
+                Some(Action::DeleteTask) => app.bulk_delete(),
+                Some(Action::Tags) => app.open_prompt(InputMode::BulkTags),
+                Some(Action::Priority) => app.open_prompt(InputMode::BulkPriority),
+                Some(Action::Due) => app.open_prompt(InputMode::BulkDue),
+                Some(Action::Move) => app.open_prompt(InputMode::BulkMove),
+                Some(Action::Export) => app.open_prompt(InputMode::Export),
+                _ => {}
+            },
+            models::app::InputMode::FilterMode => match resolve(&mut app, &[Context::Filter], &event.into()) {
+                Some(Action::Close) => app.input_mode = InputMode::Normal,
+                Some(Action::FilterBy(filter)) => app.filter_items(filter),
+                Some(Action::Field) if !app.custom_fields.is_empty() => {
+                    app.open_prompt(InputMode::FieldFilter)
+                }
+                Some(Action::Query) => {
+                    let value = app.query.as_ref().map(|(q, _)| q.clone()).unwrap_or_default();
+                    text_areas[4] = TextArea::from(value.lines());
+                    text_areas[4].move_cursor(CursorMove::End);
+                    app.open_prompt(InputMode::Query);
+                }
+                _ => {}
+            },
+            models::app::InputMode::Details => match resolve(&mut app, &[Context::Details], &event.into()) {
+                Some(Action::Close) => app.input_mode = InputMode::Normal,
+                Some(Action::Next) => app.detail_next(),
+                Some(Action::Previous) => app.detail_previous(),
+                Some(Action::ToggleCheck) => app.toggle_check(),
+                Some(Action::OpenLink) => app.open_link(),
+                Some(Action::DeleteEntry) => app.delete_entry(),
+                Some(Action::EditComment) => {
+                    if let Some(i) = app.edit_comment() {
+                        text_areas[3] = TextArea::from(i.lines());
+                        app.input_mode = InputMode::CommentEdit;
+                        app.input_area = InputArea::Comment;
+                    }
                 }
+                Some(Action::Reply) if app.reply_comment() => {
+                    app.input_mode = InputMode::Comment;
+                    app.input_area = InputArea::Comment;
+                }
+                Some(Action::ScrollDown) => app.scroll_down(),
+                Some(Action::ScrollUp) => app.scroll_up(),
+                _ => {}
             },
+            models::app::InputMode::TemplatePicker => match resolve(&mut app, &[Context::Templates], &event.into()) {
+                Some(Action::Close) => app.input_mode = InputMode::Normal,
+                Some(Action::Next) => app.template_next(),
+                Some(Action::Previous) => app.template_previous(),
+                Some(Action::Select) => app.choose_template(),
+                _ => {}
+            },
+            models::app::InputMode::Defer
+            | models::app::InputMode::Link
+            | models::app::InputMode::TemplateFill
+            | models::app::InputMode::FieldFilter
+            | models::app::InputMode::Query
+            | models::app::InputMode::FieldSort
+            | models::app::InputMode::Export
+            | models::app::InputMode::Assign
+            | models::app::InputMode::BulkTags
+            | models::app::InputMode::BulkPriority
+            | models::app::InputMode::BulkDue
+            | models::app::InputMode::BulkMove => {
+                let input: Input = event.into();
+                match resolve(&mut app, &[Context::Prompt], &input) {
+                    Some(Action::Close) => {
+                        text_areas[4] = TextArea::default();
+                        app.close_prompt();
+                    }
+                    Some(Action::Submit) => {
+                        app.submit_prompt(&text_areas[4]);
+                        if app.prompt_error.is_none() {
+                            text_areas[4] = TextArea::default();
+                        }
+                    }
+                    _ if app.pending_keys.is_empty() => {
+                        text_areas[4].input(input);
+                    }
+                    _ => {}
+                }
+            }
+            _ => {
+                let input: Input = event.into();
+                match resolve(&mut app, &[Context::Popup], &input) {
+                    Some(Action::Close) => {
+                        text_areas[0] = TextArea::default();
+                        text_areas[1] = TextArea::default();
+                        text_areas[2] = TextArea::default();
+                        text_areas[3] = TextArea::default();
+                        text_areas[5] = TextArea::default();
+                        app.comment_target = None;
+                        app.prompt_error = None;
+                        app.input_mode = InputMode::Normal;
+                        app.input_area = InputArea::Topic;
+                    }
+                    Some(Action::Save) => {
+                        app.create(
+                            &text_areas[0],
+                            &text_areas[1],
+                            &text_areas[2],
+                            &text_areas[3],
+                            &text_areas[5],
+                        );
+                        if app.prompt_error.is_none() {
+                            text_areas[0] = TextArea::default();
+                            text_areas[1] = TextArea::default();
+                            text_areas[2] = TextArea::default();
+                            text_areas[3] = TextArea::default();
+                            text_areas[5] = TextArea::default();
+                        }
+                    }
+                    Some(Action::NextField) => app.change_input_area(),
+                    // keys that are not bound (and do not start a binding) are typed
+                    _ if app.pending_keys.is_empty() => {
+                        text_areas[app.input_area.index()].input(input);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Resolves a key press against `contexts`. The keys are kept while they
+/// start a longer sequence; `None` also when nothing is bound.
+fn resolve(app: &mut App, contexts: &[Context], input: &Input) -> Option<Action> {
+    if input.key == Key::Null {
+        return None;
+    }
+    push_input(&mut app.pending_keys, input);
+    match app.keymap.lookup(contexts, &app.pending_keys) {
+        Lookup::Pending => None,
+        Lookup::Action(action) => {
+            app.pending_keys.clear();
+            Some(action)
+        }
+        Lookup::None => {
+            app.pending_keys.clear();
+            None
+        }
+    }
+}
+
+/// Routes a mouse event using the screen areas of the last frame: clicks
+/// select list rows, popup fields and filter options, the wheel scrolls the
+/// pane under the pointer and the split divider can be dragged.
+fn handle_mouse(app: &mut App, mouse: MouseEvent) {
+    let (x, y) = (mouse.column, mouse.row);
+    let inside = |area: Rect| x >= area.x && x < area.right() && y >= area.y && y < area.bottom();
+    let list = app.areas.list;
+    let details = app.areas.details;
+    match (app.input_mode, mouse.kind) {
+        (_, MouseEventKind::Up(MouseButton::Left)) => app.dragging = false,
+        (InputMode::Normal, MouseEventKind::Drag(MouseButton::Left)) if app.dragging => app.drag_split(x, y),
+        (InputMode::Normal, MouseEventKind::Down(MouseButton::Left)) if app.on_divider(x, y) => {
+            app.dragging = true
+        }
+        (InputMode::Normal | InputMode::Details, MouseEventKind::Down(MouseButton::Left))
+            if inside(list) && app.layout != PageLayout::Board && y > list.y && y + 1 < list.bottom() =>
+        {
+            app.input_mode = InputMode::Normal;
+            app.click_row((y - list.y - 1) as usize);
+        }
+        (InputMode::Normal | InputMode::Details, MouseEventKind::ScrollDown) if inside(details) => {
+            app.scroll_down()
+        }
+        (InputMode::Normal | InputMode::Details, MouseEventKind::ScrollUp) if inside(details) => {
+            app.scroll_up()
+        }
+        (InputMode::Normal, MouseEventKind::ScrollDown) if inside(list) => app.next(),
+        (InputMode::Normal, MouseEventKind::ScrollUp) if inside(list) => app.previous(),
+        (InputMode::FilterMode, MouseEventKind::Down(MouseButton::Left)) => {
+            let popup = app.areas.filter_popup;
+            if inside(popup) && y > popup.y {
+                app.click_filter((y - popup.y - 1) as usize);
+            }
+        }
+        (
+            InputMode::Editing | InputMode::Modify | InputMode::SubTask | InputMode::SubTaskModify,
+            MouseEventKind::Down(MouseButton::Left),
+        ) => {
+            if let Some((_, area)) = app.areas.fields.iter().rev().find(|(r, _)| inside(*r)) {
+                app.input_area = *area;
+            }
         }
+        _ => {}
     }
 }
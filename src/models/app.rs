@@ -1,10 +1,19 @@
 use super::{
+    archive::{self, Archive},
+    command::{complete, Command},
+    config::{AppConfig, CustomField, View},
+    export::{to_json, to_markdown},
+    fuzzy::fuzzy_match,
+    keymap::{Chord, Keymap},
     os::FileSystem,
-    task::{Status, Task},
+    query::Query,
+    task::{Link, LinkKind, Priority, Status, Task},
+    template::Template,
+    time::{current_timestamp, day_start, parse_date, week_start, DAY},
 };
 use core::fmt;
-use ratatui::widgets::*;
-use std::fs;
+use ratatui::{layout::Rect, widgets::*};
+use std::{cmp::Ordering, collections::BTreeMap, fs, path::Path};
 use tui_textarea::TextArea;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -14,6 +23,8 @@ pub enum Filter {
     InProgress,
     Done,
     NotDone,
+    Deferred,
+    Mine,
     All,
 }
 
@@ -25,22 +36,130 @@ impl Filter {
             Filter::InProgress,
             Filter::Done,
             Filter::NotDone,
+            Filter::Deferred,
+            Filter::Mine,
             Filter::All,
         ]
         .iter()
         .copied()
     }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Filter::New => "New",
+            Filter::Hold => "Hold",
+            Filter::InProgress => "In Progress",
+            Filter::Done => "Done",
+            Filter::NotDone => "Not Done",
+            Filter::Deferred => "Deferred",
+            Filter::Mine => "Mine",
+            Filter::All => "All",
+        }
+    }
+}
+
+/// Order of the task list. Sub tasks always stay right after their parent
+/// and are ordered among themselves by the same key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SortKey {
+    Created,
+    StatusChange,
+    Status,
+    Topic,
+    Name,
+    Priority,
+    Due,
+    Field(String),
+}
+
+impl SortKey {
+    pub fn iterator() -> impl Iterator<Item = SortKey> {
+        [
+            SortKey::Created,
+            SortKey::StatusChange,
+            SortKey::Status,
+            SortKey::Topic,
+            SortKey::Name,
+            SortKey::Priority,
+            SortKey::Due,
+        ]
+        .into_iter()
+    }
+
+    /// Parses `created`, `updated`, `status`, `topic`, `name`, `priority`,
+    /// `due` or the name of a custom field.
+    fn parse(name: &str, fields: &[CustomField]) -> Result<SortKey, String> {
+        match name {
+            "created" => Ok(SortKey::Created),
+            "updated" | "changed" => Ok(SortKey::StatusChange),
+            "status" => Ok(SortKey::Status),
+            "topic" => Ok(SortKey::Topic),
+            "name" => Ok(SortKey::Name),
+            "priority" => Ok(SortKey::Priority),
+            "due" => Ok(SortKey::Due),
+            _ if fields.iter().any(|f| f.name == name) => Ok(SortKey::Field(name.to_string())),
+            _ => Err(format!(
+                "unknown sort '{}' (created, updated, status, topic, name, priority, due or a custom field)",
+                name
+            )),
+        }
+    }
+
+    /// Compares two tasks; tasks without a value go last in both directions.
+    fn compare(&self, a: &Task, b: &Task, descending: bool, fields: &[CustomField]) -> Ordering {
+        fn status_rank(s: Status) -> u8 {
+            match s {
+                Status::New => 0,
+                Status::InProgress => 1,
+                Status::Hold => 2,
+                Status::Done => 3,
+            }
+        }
+        fn last<T: Ord>(a: Option<T>, b: Option<T>, descending: bool) -> Ordering {
+            match (a, b) {
+                (Some(x), Some(y)) if descending => y.cmp(&x),
+                (Some(x), Some(y)) => x.cmp(&y),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }
+        }
+        match self {
+            SortKey::Created => last(Some(a.creation_timestamp), Some(b.creation_timestamp), descending),
+            SortKey::StatusChange => last(a.status_change_timestamp, b.status_change_timestamp, descending),
+            SortKey::Status => last(Some(status_rank(a.status)), Some(status_rank(b.status)), descending),
+            SortKey::Topic => last(Some(a.topic.to_lowercase()), Some(b.topic.to_lowercase()), descending),
+            SortKey::Name => last(Some(a.name.to_lowercase()), Some(b.name.to_lowercase()), descending),
+            SortKey::Priority => last(a.priority, b.priority, descending),
+            SortKey::Due => last(a.due_timestamp, b.due_timestamp, descending),
+            SortKey::Field(name) => {
+                let field = match fields.iter().find(|f| f.name == *name) {
+                    Some(f) => f,
+                    None => return Ordering::Equal,
+                };
+                match (a.fields.get(name), b.fields.get(name)) {
+                    (Some(x), Some(y)) if descending => field.compare(y, x),
+                    (Some(x), Some(y)) => field.compare(x, y),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                }
+            }
+        }
+    }
 }
 
-impl fmt::Display for Filter {
+impl fmt::Display for SortKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Filter::New => write!(f, "<n> by [New]"),
-            Filter::Hold => write!(f, "<h> by [Hold]"),
-            Filter::InProgress => write!(f, "<i> by [In Progress]"),
-            Filter::Done => write!(f, "<d> by [Done]"),
-            Filter::NotDone => write!(f, "<o> by [Not Done]"),
-            Filter::All => write!(f, "<a> by [All]"),
+            SortKey::Created => write!(f, "created"),
+            SortKey::StatusChange => write!(f, "updated"),
+            SortKey::Status => write!(f, "status"),
+            SortKey::Topic => write!(f, "topic"),
+            SortKey::Name => write!(f, "name"),
+            SortKey::Priority => write!(f, "priority"),
+            SortKey::Due => write!(f, "due"),
+            SortKey::Field(name) => write!(f, "{}", name),
         }
     }
 }
@@ -56,12 +175,66 @@ pub enum InputMode {
     SubTaskModify,
     Help,
     FilterMode,
+    Defer,
+    Details,
+    Link,
+    TemplatePicker,
+    TemplateFill,
+    FieldFilter,
+    FieldSort,
+    Export,
+    Assign,
+    Visual,
+    Bulk,
+    BulkTags,
+    BulkPriority,
+    BulkDue,
+    BulkMove,
+    Archive,
+    ArchiveSearch,
+    Search,
+    Query,
+    ViewPicker,
+    Sort,
+    GoTo,
+    Stats,
+    Command,
+}
+
+/// A line of the task list: a topic header in grouped mode, or a task given
+/// by its index in `items`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Row {
+    Header(String),
+    Task(usize),
+}
+
+/// Entries of the Details pane that can be selected with the cursor.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum DetailEntry {
+    Check(usize),
+    Link(usize),
+    Comment(usize),
 }
 
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub enum PageLayout {
     Vertical,
     Horizontal,
+    Board,
+    Calendar,
+}
+
+/// Columns of the board layout, left to right.
+pub const BOARD_COLUMNS: [Status; 4] = [Status::New, Status::InProgress, Status::Hold, Status::Done];
+
+/// Screen areas of the last drawn frame, used to route mouse events.
+#[derive(Default, Clone)]
+pub struct ScreenAreas {
+    pub list: Rect,
+    pub details: Rect,
+    pub fields: Vec<(Rect, InputArea)>,
+    pub filter_popup: Rect,
 }
 
 #[derive(Copy, Clone)]
@@ -70,6 +243,8 @@ pub enum InputArea {
     Task,
     Description,
     Comment,
+    Prompt,
+    Fields,
 }
 
 impl InputArea {
@@ -78,62 +253,314 @@ impl InputArea {
     }
 }
 
+const HISTORY_LIMIT: usize = 100;
+/// Name of the task list file under `~/.todo`; its stem is the list name.
+const LIST_FILE: &str = "todo.json";
+/// How long a status bar message stays, in seconds.
+const MESSAGE_SECONDS: i64 = 3;
+
+/// A transient status bar note such as "Saved" or an error.
+#[derive(Clone, Debug)]
+pub struct Message {
+    pub text: String,
+    pub error: bool,
+    pub at: i64,
+}
+
+impl Message {
+    pub fn expired(&self, now: i64) -> bool {
+        now - self.at >= MESSAGE_SECONDS
+    }
+}
+
 pub struct App {
     pub scroll_state: ScrollbarState,
     pub scroll: usize,
     pub state: ListState,
+    pub list_offset: usize,
+    pub group_by_topic: bool,
+    pub collapsed: Vec<String>,
+    pub selected_header: Option<String>,
     pub layout: PageLayout,
+    pub board_column: usize,
+    pub calendar_date: Option<i64>,
+    pub split: u16,
+    pub dragging: bool,
+    pub areas: ScreenAreas,
+    pub keymap: Keymap,
+    pub pending_keys: Vec<Chord>,
+    pub help_scroll: u16,
+    pub command_history: Vec<String>,
+    pub history_position: Option<usize>,
+    pub completions: Vec<String>,
+    pub message: Option<Message>,
+    /// Why the last save of `list_path` failed; cleared by the next one
+    /// that succeeds.
+    pub save_error: Option<String>,
+    list_path: String,
     pub items: Vec<Task>,
     pub sub_items: Vec<Task>,
     pub input_mode: InputMode,
     pub input_area: InputArea,
     pub filter: Filter,
+    pub prompt_error: Option<String>,
+    pub status_rollup: bool,
+    pub detail_cursor: usize,
+    pub comment_target: Option<i64>,
+    pub templates: Vec<Template>,
+    pub template_state: ListState,
+    pub template_error: Option<String>,
+    pub template_values: Vec<(String, String)>,
+    pub template_field: usize,
+    pub custom_fields: Vec<CustomField>,
+    pub field_filter: Option<(String, String)>,
+    pub user_name: String,
+    pub marked: Vec<i64>,
+    pub visual_anchor: Option<usize>,
+    pub search_query: String,
+    pub query: Option<(String, Query)>,
+    pub sort: Option<(SortKey, bool)>,
+    pub views: Vec<View>,
+    pub view: Option<usize>,
+    pub view_state: ListState,
+    pub view_error: Option<String>,
+    pub archive: Vec<Task>,
+    archive_error: Option<String>,
+    pub archive_view: Vec<usize>,
+    pub archive_state: ListState,
+    pub archive_query: String,
+    pub goto_results: Vec<usize>,
+    pub goto_state: ListState,
+    archive_after_days: Option<i64>,
+    saved: (Vec<Task>, Vec<Task>),
+    history: Vec<(Vec<Task>, Vec<Task>)>,
+    last_tick: i64,
 }
 
 impl App {
-    fn read() -> Vec<Task> {
-        let path = App::get_path();
-        let file_path = format!("{}/todo.json", path);
+    fn read(file_path: &str) -> Vec<Task> {
         let file_content = fs::read_to_string(file_path);
         let tasks = {
             match file_content {
-                Ok(s) => serde_json::from_str::<Vec<Task>>(&s).unwrap(),
+                Ok(s) => {
+                    let mut tasks = serde_json::from_str::<Vec<Task>>(&s).unwrap();
+                    for task in tasks.iter_mut() {
+                        task.normalize_comment_ids();
+                    }
+                    tasks
+                }
                 Err(_) => vec![Task {
                     id: 0,
-                    topic: "main".to_string(),
-                    status: Status::New,
-                    name: "Hello this is default task".to_string(),
-                    description: "Some description...".to_string(),
-                    creation_timestamp: 1,
-                    creation_date: "1".to_string(),
-                    status_change_timestamp: None,
-                    status_change_date: None,
-                    duration: None,
-                    comments: Vec::new(),
-                    child_list: Vec::new(),
-                    parent_id: None,
-                    is_sub_task: false,
-                    display: true,
+                    ..Task::create(
+                        None,
+                        "Hello this is default task".to_string(),
+                        Some("Some description...".to_string()),
+                        None,
+                        None,
+                    )
                 }],
             }
         };
         return tasks;
     }
 
+    /// Saves the tasks; the previously saved state becomes an undo step.
     pub fn write(&mut self) {
-        let path = App::get_path();
-        let file_path = format!("{}/todo.json", path);
-        let _ = std::fs::write(
-            file_path,
+        self.sort_items();
+        let previous = std::mem::replace(
+            &mut self.saved,
+            (self.items.clone(), self.archive.clone()),
+        );
+        self.history.push(previous);
+        if self.history.len() > HISTORY_LIMIT {
+            self.history.remove(0);
+        }
+        self.save_file();
+    }
+
+    fn save_file(&mut self) {
+        let result = std::fs::write(
+            &self.list_path,
             serde_json::to_string_pretty(&self.items).unwrap(),
         );
+        match result {
+            Ok(()) => self.save_error = None,
+            Err(e) => {
+                let error = format!("could not save {}: {}", self.list_path, e);
+                self.notify(error.clone(), true);
+                self.save_error = Some(error);
+            }
+        }
+    }
+
+    /// Stem of the loaded list file, e.g. `todo`.
+    pub fn list_name(&self) -> &str {
+        Path::new(&self.list_path)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+    }
+
+    /// Saves the archive, unless it could not be read at startup: writing
+    /// then would replace the tasks still in the file.
+    fn write_archive(&self) -> Result<(), String> {
+        match &self.archive_error {
+            Some(e) => Err(format!("archive not saved, it could not be read ({})", e)),
+            None => Archive::write(&self.archive),
+        }
+    }
+
+    pub fn notify(&mut self, text: String, error: bool) {
+        self.message = Some(Message {
+            text,
+            error,
+            at: current_timestamp(),
+        });
+    }
+
+    pub fn undo(&mut self) {
+        if let Some((items, archive)) = self.history.pop() {
+            if archive != self.archive {
+                let previous = std::mem::replace(&mut self.archive, archive);
+                if let Err(e) = self.write_archive() {
+                    // keep the step so that it can be retried
+                    let archive = std::mem::replace(&mut self.archive, previous);
+                    self.history.push((items, archive));
+                    self.notify(e, true);
+                    return;
+                }
+                self.refresh_archive();
+            }
+            self.items = items;
+            self.sort_items();
+            self.saved = (self.items.clone(), self.archive.clone());
+            self.marked.retain(|id| self.items.iter().any(|t| t.id == *id));
+            self.apply_filter();
+            self.save_file();
+            self.clamp_selection();
+        } else {
+            self.notify("nothing to undo".to_string(), false);
+        }
+    }
+
+    /// Lines of the task list. In grouped mode every topic gets a header
+    /// followed by its tasks, unless the group is collapsed.
+    pub fn rows(&self) -> Vec<Row> {
+        let displayed = (0..self.items.len()).filter(|i| self.items[*i].display);
+        if !self.group_by_topic {
+            return displayed.map(Row::Task).collect();
+        }
+        let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+        for i in displayed {
+            let topic = self.topic_of(i);
+            match groups.iter_mut().find(|(t, _)| *t == topic) {
+                Some((_, members)) => members.push(i),
+                None => groups.push((topic, vec![i])),
+            }
+        }
+        let mut rows = Vec::new();
+        for (topic, members) in groups {
+            let collapsed = self.collapsed.contains(&topic);
+            rows.push(Row::Header(topic));
+            if !collapsed {
+                rows.extend(members.into_iter().map(Row::Task));
+            }
+        }
+        rows
+    }
+
+    /// Topic a task is grouped under: its parent's topic for sub tasks.
+    pub fn topic_of(&self, i: usize) -> String {
+        let task = &self.items[i];
+        match task.parent_id.and_then(|p| self.index_by_id(p)) {
+            Some(p) if task.is_sub_task => self.items[p].topic.clone(),
+            _ => task.topic.clone(),
+        }
+    }
+
+    /// Store indices of the displayed tasks, in the order the list shows
+    /// them.
+    pub fn visible_rows(&self) -> Vec<usize> {
+        self.rows()
+            .into_iter()
+            .filter_map(|r| match r {
+                Row::Task(i) => Some(i),
+                Row::Header(_) => None,
+            })
+            .collect()
+    }
+
+    fn selected(&self) -> Option<Row> {
+        match (&self.selected_header, self.state.selected()) {
+            (Some(topic), _) => Some(Row::Header(topic.clone())),
+            (None, Some(i)) => Some(Row::Task(i)),
+            (None, None) => None,
+        }
+    }
+
+    /// List row of the selected task or header.
+    pub fn selected_row(&self) -> Option<usize> {
+        let selected = self.selected()?;
+        self.rows().iter().position(|r| *r == selected)
+    }
+
+    fn select_row(&mut self, row: Option<Row>) {
+        self.detail_cursor = 0;
+        match row {
+            Some(Row::Header(topic)) => {
+                self.selected_header = Some(topic);
+                self.state.select(None);
+                self.sub_items.clear();
+            }
+            Some(Row::Task(i)) => {
+                self.selected_header = None;
+                self.get_child_list(Some(i));
+                self.state.select(Some(i));
+            }
+            None => {
+                self.selected_header = None;
+                self.state.select(None);
+                self.sub_items.clear();
+            }
+        }
+    }
+
+    /// Moves the selection off a hidden (or removed) task: to the header of
+    /// its collapsed group, the next displayed task, the last one, or nothing.
+    fn clamp_selection(&mut self) {
+        let rows = self.rows();
+        let current = self.selected();
+        if current.as_ref().is_some_and(|r| rows.contains(r)) {
+            if let Some(Row::Task(i)) = current {
+                self.get_child_list(Some(i));
+            }
+            return;
+        }
+        let selected = match current {
+            Some(Row::Task(i)) if i < self.items.len() && self.items[i].display && self.group_by_topic => {
+                Some(Row::Header(self.topic_of(i)))
+            }
+            Some(Row::Task(i)) => rows
+                .iter()
+                .find(|r| matches!(r, Row::Task(t) if *t > i))
+                .or(rows.last())
+                .cloned(),
+            _ => rows.first().cloned(),
+        };
+        self.scroll = 0;
+        self.select_row(selected);
     }
 
-    pub fn new() -> App {
-        let tasks = App::read();
-        App {
+    pub fn new(conf: &AppConfig) -> App {
+        let list_path = format!("{}/{}", App::get_path(), LIST_FILE);
+        let tasks = App::read(&list_path);
+        let mut app = App {
             scroll_state: ScrollbarState::default(),
             scroll: 0,
+            list_offset: 0,
+            group_by_topic: false,
+            collapsed: Vec::new(),
+            selected_header: None,
             state: {
                 if tasks.len() > 0 {
                     let mut st = ListState::default();
@@ -144,12 +571,69 @@ impl App {
                 }
             },
             layout: PageLayout::Vertical,
+            board_column: 0,
+            calendar_date: None,
+            split: 50,
+            dragging: false,
+            areas: ScreenAreas::default(),
+            keymap: Keymap::default(),
+            pending_keys: Vec::new(),
+            help_scroll: 0,
+            command_history: Vec::new(),
+            history_position: None,
+            completions: Vec::new(),
+            message: None,
+            save_error: None,
+            list_path,
             items: tasks,
             sub_items: Vec::new(),
             input_mode: InputMode::Normal,
             input_area: InputArea::Topic,
             filter: Filter::All,
+            prompt_error: None,
+            status_rollup: conf.behavior.status_rollup == "auto",
+            detail_cursor: 0,
+            comment_target: None,
+            templates: Vec::new(),
+            template_state: ListState::default(),
+            template_error: None,
+            template_values: Vec::new(),
+            template_field: 0,
+            custom_fields: conf.custom_fields.clone(),
+            field_filter: None,
+            user_name: conf.user.name.clone(),
+            marked: Vec::new(),
+            visual_anchor: None,
+            search_query: String::new(),
+            query: None,
+            sort: None,
+            views: conf.views.clone(),
+            view: None,
+            view_state: ListState::default(),
+            view_error: None,
+            archive: Vec::new(),
+            archive_error: None,
+            archive_view: Vec::new(),
+            archive_state: ListState::default(),
+            archive_query: String::new(),
+            goto_results: Vec::new(),
+            goto_state: ListState::default(),
+            archive_after_days: conf.behavior.archive_after_days,
+            saved: (Vec::new(), Vec::new()),
+            history: Vec::new(),
+            last_tick: current_timestamp(),
+        };
+        match Archive::read() {
+            Ok(archive) => app.archive = archive,
+            Err(e) => {
+                app.notify(format!("could not read the archive: {}", e), true);
+                app.archive_error = Some(e);
+            }
         }
+        app.saved = (app.items.clone(), app.archive.clone());
+        app.apply_filter();
+        app.auto_archive();
+        app
     }
 
     pub fn create(
@@ -158,23 +642,24 @@ impl App {
         name: &TextArea,
         descripiton: &TextArea,
         comment: &TextArea,
+        fields: &TextArea,
     ) {
         match self.input_mode {
-            InputMode::CommentEdit => match self.state.selected() {
-                Some(i) => {
-                    self.delete_comment();
+            InputMode::CommentEdit => {
+                if let (Some(i), Some(id)) = (self.state.selected(), self.comment_target.take()) {
                     let _comment: String = comment.clone().into_lines().join("\n").to_string();
-                    self.items[i].create_comment(_comment);
+                    self.items[i].edit_comment(id, _comment);
                     self.write();
                     self.input_mode = InputMode::Normal;
                     self.input_area = InputArea::Topic;
                 }
-                None => (),
-            },
+            }
             InputMode::Comment => match self.state.selected() {
                 Some(i) => {
                     let _comment: String = comment.clone().into_lines().join("\n").to_string();
-                    self.items[i].create_comment(_comment);
+                    let reply_to = self.comment_target.take();
+                    let author = Some(self.user_name.clone()).filter(|n| !n.is_empty());
+                    self.items[i].create_comment(_comment, reply_to, author);
                     self.write();
                     self.input_mode = InputMode::Normal;
                     self.input_area = InputArea::Topic;
@@ -184,11 +669,21 @@ impl App {
             _ => {
                 let _name: String = name.clone().into_lines().concat().to_string();
                 if !_name.is_empty() {
+                    let _fields = match self.parse_fields(fields) {
+                        Ok(f) => f,
+                        Err(e) => {
+                            self.prompt_error = Some(e);
+                            return;
+                        }
+                    };
+                    self.prompt_error = None;
                     let _topic: String = topic.clone().into_lines().concat().to_string();
                     let _description: String =
                         descripiton.clone().into_lines().join("\n").to_string();
                     let mut task =
                         Task::create(Some(_topic), _name, Some(_description), None, None);
+                    task.id = self.unique_id();
+                    task.fields = _fields.clone();
 
                     match self.input_mode {
                         InputMode::Modify | InputMode::SubTaskModify => match self.state.selected()
@@ -201,6 +696,7 @@ impl App {
                                     descripiton.clone().into_lines().join("\n").to_string();
                                 modify_task.child_list = self.items[s].child_list.clone();
                                 modify_task.is_sub_task = self.items[s].is_sub_task;
+                                modify_task.fields = _fields;
                                 self.items[s] = modify_task;
                             }
                             None => (),
@@ -222,6 +718,7 @@ impl App {
                                     parent_index + self.items[parent_index].child_list.len(),
                                     task,
                                 );
+                                self.roll_up(self.items[parent_index].id);
                             }
                             None => (),
                         },
@@ -240,53 +737,50 @@ impl App {
     pub fn delete(&mut self) {
         match self.state.selected() {
             Some(i) => {
-                let task = self.items[i].clone();
-                if task.is_sub_task {
-                    let p_task = self
-                        .items
-                        .iter_mut()
-                        .enumerate()
-                        .filter(|(_idx, f)| f.id == task.parent_id.unwrap())
-                        .nth(0);
-                    match p_task {
-                        Some(item) => {
-                            let (idx, parent_task) = item;
-                            let mut clone_task = parent_task.clone();
-                            let mod_sub_task: Vec<_> = clone_task
-                                .child_list
-                                .iter()
-                                .filter(|id| *id != &task.id)
-                                .cloned()
-                                .collect();
-                            clone_task.child_list = mod_sub_task;
-                            self.items[idx] = clone_task;
-                        }
-                        None => (),
-                    }
-                    self.items.remove(i);
-                    self.write();
-                    self.next();
-                } else if task.child_list.len() > 0 {
-                    let new_items: Vec<_> = self
-                        .items
-                        .iter()
-                        .filter(|x| !task.child_list.contains(&x.id))
-                        .filter(|x| task.id != x.id)
-                        .cloned()
-                        .collect();
-                    self.items = new_items;
-                    self.write();
-                    self.next();
-                } else {
-                    self.items.remove(i);
-                    self.write();
-                    self.next();
-                }
+                self.remove_task(i);
+                self.apply_filter();
+                self.write();
             }
             None => (),
         };
     }
 
+    fn remove_task(&mut self, i: usize) {
+        let task = self.items[i].clone();
+        if task.is_sub_task {
+            let p_task = self
+                .items
+                .iter_mut()
+                .enumerate()
+                .filter(|(_idx, f)| f.id == task.parent_id.unwrap())
+                .nth(0);
+            if let Some((idx, parent_task)) = p_task {
+                let mut clone_task = parent_task.clone();
+                let mod_sub_task: Vec<_> = clone_task
+                    .child_list
+                    .iter()
+                    .filter(|id| *id != &task.id)
+                    .cloned()
+                    .collect();
+                clone_task.child_list = mod_sub_task;
+                self.items[idx] = clone_task;
+            }
+            self.items.remove(i);
+            self.roll_up(task.parent_id.unwrap());
+        } else if !task.child_list.is_empty() {
+            let new_items: Vec<_> = self
+                .items
+                .iter()
+                .filter(|x| !task.child_list.contains(&x.id))
+                .filter(|x| task.id != x.id)
+                .cloned()
+                .collect();
+            self.items = new_items;
+        } else {
+            self.items.remove(i);
+        }
+    }
+
     pub fn delete_comment(&mut self) {
         match self.state.selected() {
             Some(i) => {
@@ -297,24 +791,83 @@ impl App {
         };
     }
 
-    pub fn edit(&mut self) -> Option<(String, String, String, bool)> {
+    /// Text of the comment selected in the Details pane; the comment becomes
+    /// the target of the next save.
+    pub fn edit_comment(&mut self) -> Option<String> {
+        match (self.state.selected(), self.detail_entry()) {
+            (Some(i), Some(DetailEntry::Comment(c))) => {
+                let comment = &self.items[i].comments[c];
+                self.comment_target = Some(comment.id);
+                Some(comment.text.clone())
+            }
+            _ => None,
+        }
+    }
+
+    pub fn reply_comment(&mut self) -> bool {
+        match (self.state.selected(), self.detail_entry()) {
+            (Some(i), Some(DetailEntry::Comment(c))) => {
+                self.comment_target = Some(self.items[i].comments[c].id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn edit(&mut self) -> Option<(String, String, String, bool, String)> {
         let data = match self.state.selected() {
             Some(i) => Some((
                 self.items.get(i).unwrap().topic.clone(),
                 self.items.get(i).unwrap().name.clone(),
                 self.items.get(i).unwrap().description.clone(),
                 self.items.get(i).unwrap().is_sub_task.clone(),
+                self.fields_text(Some(i)),
             )),
             None => None,
         };
         return data;
     }
 
+    /// One `name: value` line per declared custom field, prefilled from the
+    /// task at `index`.
+    pub fn fields_text(&self, index: Option<usize>) -> String {
+        self.custom_fields
+            .iter()
+            .map(|f| {
+                let value = index
+                    .and_then(|i| self.items[i].fields.get(&f.name))
+                    .cloned()
+                    .unwrap_or_default();
+                format!("{}: {}", f.name, value)
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn parse_fields(&self, fields: &TextArea) -> Result<BTreeMap<String, String>, String> {
+        let mut values = BTreeMap::new();
+        for line in fields.lines().iter().filter(|l| !l.trim().is_empty()) {
+            let (name, value) = match line.split_once(':') {
+                Some((n, v)) => (n.trim(), v.trim()),
+                None => return Err(format!("expected 'name: value', got '{}'", line)),
+            };
+            let field = match self.custom_fields.iter().find(|f| f.name == name) {
+                Some(f) => f,
+                None => return Err(format!("unknown field '{}'", name)),
+            };
+            if !value.is_empty() {
+                values.insert(name.to_string(), field.validate(value)?);
+            }
+        }
+        Ok(values)
+    }
+
     pub fn edit_last_comment(&mut self) -> Option<String> {
         let data = match self.state.selected() {
             Some(i) => {
                 let comment = self.items.get(i).unwrap().comments.last();
                 if let Some(c) = comment {
+                    self.comment_target = Some(c.id);
                     Some(c.text.clone())
                 } else {
                     None
@@ -325,106 +878,1664 @@ impl App {
         return data;
     }
 
-    pub fn change_status(&mut self) {
+    pub fn assignee_value(&mut self) -> Option<String> {
         match self.state.selected() {
-            Some(i) => {
-                let mut subtask_done_filter = true;
-                let task = self.items.get_mut(i).unwrap();
-                for item in self.sub_items.iter() {
-                    if item.status != Status::Done {
-                        subtask_done_filter = false;
-                    }
-                }
-                task.change_status(subtask_done_filter);
-            }
-            None => (),
-        };
-        self.write();
+            Some(i) => Some(self.items[i].assignee.clone().unwrap_or_default()),
+            None => None,
+        }
     }
 
-    fn get_child_list(&mut self, some_item: Option<usize>) {
-        match some_item {
-            Some(i) => {
-                let child_list = self.items.get(i).unwrap().child_list.clone();
-                let sub_task: Vec<Task> = self
-                    .items
-                    .iter()
-                    .filter(|f| child_list.iter().any(|&item| item == f.id))
-                    .map(|x| x.clone())
-                    .collect();
-                self.sub_items = sub_task;
-            }
-            None => (),
+    pub fn defer_value(&mut self) -> Option<String> {
+        match self.state.selected() {
+            Some(i) => Some(self.items[i].defer_date.clone().unwrap_or_default()),
+            None => None,
         }
     }
 
-    pub fn next(&mut self) {
-        let i = match self.state.selected() {
-            Some(i) => {
-                if self.items.len() > 0 && i >= self.items.len() - 1 {
-                    Some(0)
-                } else {
-                    Some(i + 1)
+    pub fn submit_prompt(&mut self, prompt: &TextArea) {
+        let value: String = prompt.lines().concat().trim().to_string();
+        match self.input_mode {
+            InputMode::Link => match self.parse_link(&value) {
+                Ok(link) => {
+                    if let Some(i) = self.state.selected() {
+                        self.items[i].links.push(link);
+                        self.write();
+                    }
+                }
+                Err(e) => {
+                    self.prompt_error = Some(e);
+                    return;
                 }
+            },
+            InputMode::TemplateFill => {
+                if let Some(v) = self.template_values.get_mut(self.template_field) {
+                    v.1 = value;
+                }
+                self.template_field += 1;
+                if self.template_field < self.template_values.len() {
+                    self.prompt_error = None;
+                    return;
+                }
+                self.create_from_template();
             }
-            None => {
-                if self.items.len() != 0 {
-                    Some(0)
+            InputMode::FieldFilter => {
+                if value.is_empty() {
+                    self.field_filter = None;
                 } else {
+                    match value.split_once('=') {
+                        Some((name, v)) if self.custom_fields.iter().any(|f| f.name == name.trim()) => {
+                            self.field_filter = Some((name.trim().to_string(), v.trim().to_string()));
+                        }
+                        _ => {
+                            self.prompt_error = Some(format!("expected 'field=value', got '{}'", value));
+                            return;
+                        }
+                    }
+                }
+                self.view = None;
+                self.apply_filter();
+            }
+            InputMode::Query => {
+                if let Err(e) = self.set_query(&value) {
+                    self.prompt_error = Some(e);
+                    return;
+                }
+                self.view = None;
+            }
+            InputMode::FieldSort => {
+                if let Err(e) = self.apply_sort(&value) {
+                    self.prompt_error = Some(e);
+                    return;
+                }
+                self.view = None;
+            }
+            InputMode::Assign => {
+                if let Some(i) = self.state.selected() {
+                    let name = value.trim_start_matches('@').to_string();
+                    self.items[i].assignee = Some(name).filter(|n| !n.is_empty());
+                    self.apply_filter();
+                    self.write();
+                }
+            }
+            InputMode::Export => {
+                match self.export(&value) {
+                    Ok(path) => self.notify(format!("exported to {}", path), false),
+                    Err(e) => {
+                        self.prompt_error = Some(e);
+                        return;
+                    }
+                }
+            }
+            InputMode::Defer => {
+                let timestamp = if value.is_empty() {
                     None
+                } else {
+                    match parse_date(&value) {
+                        Some(t) => Some(t),
+                        None => {
+                            self.prompt_error = Some(format!("invalid date: {}", value));
+                            return;
+                        }
+                    }
+                };
+                if let Some(i) = self.state.selected() {
+                    self.items[i].defer(timestamp);
+                    self.apply_filter();
+                    self.write();
                 }
             }
+            InputMode::BulkTags => self.bulk_update(|t| t.retag(&value)),
+            InputMode::BulkPriority => {
+                let priority = if value.is_empty() {
+                    None
+                } else {
+                    match value.parse::<Priority>() {
+                        Ok(p) => Some(p),
+                        Err(e) => {
+                            self.prompt_error = Some(e);
+                            return;
+                        }
+                    }
+                };
+                self.bulk_update(|t| t.priority = priority);
+            }
+            InputMode::BulkDue => {
+                let timestamp = if value.is_empty() {
+                    None
+                } else {
+                    match parse_date(&value) {
+                        Some(t) => Some(t),
+                        None => {
+                            self.prompt_error = Some(format!("invalid date: {}", value));
+                            return;
+                        }
+                    }
+                };
+                self.bulk_update(|t| t.set_due(timestamp));
+            }
+            InputMode::BulkMove => {
+                let parent = if value.is_empty() {
+                    None
+                } else {
+                    match self.find_parent(&value) {
+                        Ok(id) => Some(id),
+                        Err(e) => {
+                            self.prompt_error = Some(e);
+                            return;
+                        }
+                    }
+                };
+                self.bulk_move(parent);
+            }
+            _ => (),
+        }
+        self.close_prompt();
+    }
+
+    pub fn open_prompt(&mut self, mode: InputMode) {
+        let needs_task = matches!(mode, InputMode::Link | InputMode::Defer | InputMode::Assign);
+        if !needs_task || self.state.selected().is_some() {
+            self.prompt_error = None;
+            self.input_mode = mode;
+            self.input_area = InputArea::Prompt;
+        }
+    }
+
+    pub fn open_templates(&mut self) {
+        match Template::load_all() {
+            Ok(templates) => {
+                self.templates = templates;
+                self.template_error = None;
+            }
+            Err(e) => {
+                self.templates = Vec::new();
+                self.template_error = Some(e);
+            }
+        }
+        self.template_state
+            .select(if self.templates.is_empty() { None } else { Some(0) });
+        self.input_mode = InputMode::TemplatePicker;
+    }
+
+    pub fn template_next(&mut self) {
+        if let Some(i) = self.template_state.selected() {
+            self.template_state
+                .select(Some((i + 1) % self.templates.len()));
+        }
+    }
+
+    pub fn template_previous(&mut self) {
+        if let Some(i) = self.template_state.selected() {
+            let len = self.templates.len();
+            self.template_state.select(Some((i + len - 1) % len));
+        }
+    }
+
+    /// Starts prompting for the placeholders of the highlighted template, or
+    /// creates it right away when it has none.
+    pub fn choose_template(&mut self) {
+        let template = match self.template_state.selected() {
+            Some(i) => &self.templates[i],
+            None => return,
         };
-        if self.items.len() > 0 {
-            self.get_child_list(i);
-            self.state.select(i);
+        self.template_values = template
+            .placeholders()
+            .into_iter()
+            .map(|p| (p, String::new()))
+            .collect();
+        self.template_field = 0;
+        self.prompt_error = None;
+        if self.template_values.is_empty() {
+            self.create_from_template();
+            self.close_prompt();
         } else {
-            self.state.select(None);
+            self.input_mode = InputMode::TemplateFill;
+            self.input_area = InputArea::Prompt;
         }
     }
 
-    pub fn previous(&mut self) {
+    fn create_from_template(&mut self) {
+        if let Some(i) = self.template_state.selected() {
+            let (parent, children) = self.templates[i].build(&self.template_values);
+            let id = self.insert_tree(parent, children, None);
+            self.apply_filter();
+            self.write();
+            self.select_by_id(id);
+        }
+    }
+
+    /// Clones the selected task right after the original. With `with_tree`
+    /// the sub tasks and all comments are copied as well.
+    pub fn duplicate(&mut self, with_tree: bool) {
         let i = match self.state.selected() {
-            Some(i) => {
-                if i == 0 {
-                    Some(self.items.len() - 1)
-                } else {
-                    Some(i - 1)
+            Some(i) => i,
+            None => return,
+        };
+        let original = self.items[i].clone();
+        let copy = original.duplicate(with_tree);
+        let id = match original.parent_id {
+            Some(parent_id) if original.is_sub_task => {
+                let mut copy = copy;
+                copy.id = self.unique_id();
+                let id = copy.id;
+                self.items.insert(i + 1, copy);
+                if let Some(p) = self.index_by_id(parent_id) {
+                    let child_list = &mut self.items[p].child_list;
+                    let position = child_list
+                        .iter()
+                        .position(|c| *c == original.id)
+                        .map_or(child_list.len(), |pos| pos + 1);
+                    child_list.insert(position, id);
                 }
+                self.roll_up(parent_id);
+                id
             }
-            None => {
-                if self.items.len() != 0 {
-                    Some(0)
+            _ => {
+                let children: Vec<Task> = if with_tree {
+                    self.items
+                        .iter()
+                        .filter(|t| original.child_list.contains(&t.id))
+                        .map(|t| t.duplicate(true))
+                        .collect()
                 } else {
-                    None
-                }
+                    Vec::new()
+                };
+                let position = i + 1 + self.items[i + 1..]
+                    .iter()
+                    .take_while(|t| t.parent_id == Some(original.id))
+                    .count();
+                self.insert_tree(copy, children, Some(position))
             }
         };
-        self.get_child_list(i);
-        self.state.select(i);
+        self.apply_filter();
+        self.write();
+        if self.index_by_id(id).is_some_and(|i| self.items[i].display) {
+            self.select_by_id(id);
+        }
     }
 
-    pub fn change_input_area(&mut self) {
-        match self.input_area {
-            InputArea::Topic => self.input_area = InputArea::Task,
-            InputArea::Task => self.input_area = InputArea::Description,
-            InputArea::Description => self.input_area = InputArea::Topic,
-            _ => (),
-        }
+    /// Id that is not used by any task yet; ids are creation timestamps, so
+    /// tasks created within the same second get the next free value.
+    fn unique_id(&self) -> i64 {
+        let max_id = self
+            .items
+            .iter()
+            .chain(self.archive.iter())
+            .map(|t| t.id)
+            .max()
+            .unwrap_or(0);
+        current_timestamp().max(max_id + 1)
     }
 
-    fn index_by_id(&self, id: i64) -> Option<usize> {
-        for (idx, task) in self.items.iter().enumerate() {
-            if task.id == id {
-                return Some(idx);
-            }
+    /// Inserts `parent` with `children` as its sub tasks, giving every task a
+    /// fresh id. A parent is placed at `position` or appended at the end.
+    fn insert_tree(&mut self, mut parent: Task, children: Vec<Task>, position: Option<usize>) -> i64 {
+        parent.id = self.unique_id();
+        parent.child_list = Vec::new();
+        let mut tree = Vec::new();
+        for (offset, mut child) in children.into_iter().enumerate() {
+            child.id = parent.id + 1 + offset as i64;
+            child.parent_id = Some(parent.id);
+            child.is_sub_task = true;
+            child.child_list = Vec::new();
+            parent.child_list.push(child.id);
+            tree.push(child);
         }
-        return None;
+        let id = parent.id;
+        tree.insert(0, parent);
+        let position = position.unwrap_or(self.items.len()).min(self.items.len());
+        self.items.splice(position..position, tree);
+        id
     }
 
-    pub fn scroll_up(&mut self) {
-        self.scroll = self.scroll.saturating_sub(1);
-        self.scroll_state = self.scroll_state.position(self.scroll)
+    pub fn close_prompt(&mut self) {
+        self.prompt_error = None;
+        self.input_mode = InputMode::Normal;
+        self.input_area = InputArea::Topic;
+    }
+
+    /// Re-applies the filter once a deferred task becomes actionable. Called
+    /// on every event loop iteration, it does the work at most once a second.
+    pub fn tick(&mut self) {
+        let now = current_timestamp();
+        let last = self.last_tick;
+        if now == last {
+            return;
+        }
+        let woke_up = self.items.iter().any(|t| match t.defer_timestamp {
+            Some(d) => d > last && d <= now,
+            None => false,
+        });
+        if woke_up {
+            self.apply_filter();
+        }
+        if self.message.as_ref().is_some_and(|m| m.expired(now)) {
+            self.message = None;
+        }
+        self.last_tick = now;
+    }
+
+    pub fn change_status(&mut self) {
+        match self.state.selected() {
+            Some(i) => {
+                let (done, total) = self.progress(&self.items[i]);
+                let task = self.items.get_mut(i).unwrap();
+                task.change_status(done == total);
+                if let Some(parent_id) = task.parent_id {
+                    self.roll_up(parent_id);
+                }
+                self.get_child_list(Some(i));
+                self.apply_filter();
+            }
+            None => (),
+        };
+        self.write();
+    }
+
+    /// Number of done sub tasks and the total number of sub tasks.
+    pub fn progress(&self, task: &Task) -> (usize, usize) {
+        let children: Vec<&Task> = self
+            .items
+            .iter()
+            .filter(|t| task.child_list.contains(&t.id))
+            .collect();
+        let done = children.iter().filter(|t| t.status == Status::Done).count();
+        (done, children.len())
+    }
+
+    /// Derives the parent status from its sub tasks when the `auto` roll-up
+    /// policy is configured: any started child starts the parent, all done
+    /// children finish it and a reopened child reopens it.
+    fn roll_up(&mut self, parent_id: i64) {
+        if !self.status_rollup {
+            return;
+        }
+        let parent_index = match self.index_by_id(parent_id) {
+            Some(p) => p,
+            None => return,
+        };
+        let statuses: Vec<Status> = self
+            .items
+            .iter()
+            .filter(|t| self.items[parent_index].child_list.contains(&t.id))
+            .map(|t| t.status)
+            .collect();
+        if statuses.is_empty() {
+            return;
+        }
+        let started = statuses.iter().any(|s| *s != Status::New);
+        let parent = &mut self.items[parent_index];
+        if statuses.iter().all(|s| *s == Status::Done) {
+            parent.set_status(Status::Done);
+        } else if parent.status == Status::Done {
+            parent.set_status(if started { Status::InProgress } else { Status::New });
+        } else if parent.status == Status::New && started {
+            parent.set_status(Status::InProgress);
+        }
+    }
+
+    fn get_child_list(&mut self, some_item: Option<usize>) {
+        match some_item {
+            Some(i) => {
+                let child_list = self.items.get(i).unwrap().child_list.clone();
+                let sub_task: Vec<Task> = self
+                    .items
+                    .iter()
+                    .filter(|f| child_list.iter().any(|&item| item == f.id))
+                    .map(|x| x.clone())
+                    .collect();
+                self.sub_items = sub_task;
+            }
+            None => (),
+        }
+    }
+
+    pub fn detail_entries(&self) -> Vec<DetailEntry> {
+        match self.state.selected() {
+            Some(i) => self.items[i]
+                .checklist()
+                .into_iter()
+                .map(|(line, _)| DetailEntry::Check(line))
+                .chain((0..self.items[i].links.len()).map(DetailEntry::Link))
+                .chain((0..self.items[i].comments.len()).map(DetailEntry::Comment))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn detail_entry(&self) -> Option<DetailEntry> {
+        self.detail_entries().get(self.detail_cursor).copied()
+    }
+
+    pub fn focus_details(&mut self) {
+        if self.state.selected().is_some() {
+            self.detail_cursor = 0;
+            self.input_mode = InputMode::Details;
+        }
+    }
+
+    pub fn detail_next(&mut self) {
+        let len = self.detail_entries().len();
+        if len > 0 {
+            self.detail_cursor = (self.detail_cursor + 1) % len;
+        }
+    }
+
+    pub fn detail_previous(&mut self) {
+        let len = self.detail_entries().len();
+        if len > 0 {
+            self.detail_cursor = (self.detail_cursor + len - 1) % len;
+        }
+    }
+
+    fn clamp_detail_cursor(&mut self) {
+        let len = self.detail_entries().len();
+        if self.detail_cursor >= len {
+            self.detail_cursor = len.saturating_sub(1);
+        }
+    }
+
+    pub fn toggle_check(&mut self) {
+        if let (Some(i), Some(DetailEntry::Check(line))) =
+            (self.state.selected(), self.detail_entry())
+        {
+            self.items[i].toggle_checklist_item(line);
+            self.write();
+        }
+    }
+
+    /// Parses prompt input into a link: `http(s)://...` is a URL, `#<id>` or
+    /// `#<name>` refers to another task and anything else is a local path.
+    fn parse_link(&self, value: &str) -> Result<Link, String> {
+        if value.is_empty() {
+            return Err("empty link".to_string());
+        }
+        if value.starts_with("http://") || value.starts_with("https://") {
+            return Ok(Link {
+                kind: LinkKind::Url,
+                target: value.to_string(),
+            });
+        }
+        if value.starts_with('#') {
+            let task = self.find_task(value)?;
+            return Ok(Link {
+                kind: LinkKind::Task,
+                target: task.id.to_string(),
+            });
+        }
+        let target = match value.strip_prefix("~/") {
+            Some(rest) => format!("{}/{}", App::home_dir(), rest),
+            None => value.to_string(),
+        };
+        Ok(Link {
+            kind: LinkKind::File,
+            target,
+        })
+    }
+
+    pub fn link_missing(&self, link: &Link) -> bool {
+        match link.kind {
+            LinkKind::File => !App::check_if_file_exist(link.target.clone()),
+            LinkKind::Task => self.linked_task(link).is_none(),
+            LinkKind::Url => false,
+        }
+    }
+
+    pub fn linked_task(&self, link: &Link) -> Option<&Task> {
+        self.items
+            .iter()
+            .find(|t| t.id.to_string() == link.target)
+    }
+
+    /// Opens the selected link with `xdg-open` or jumps to the linked task.
+    pub fn open_link(&mut self) {
+        let link = match (self.state.selected(), self.detail_entry()) {
+            (Some(i), Some(DetailEntry::Link(l))) => self.items[i].links[l].clone(),
+            _ => return,
+        };
+        match link.kind {
+            LinkKind::Task => {
+                if let Some(id) = self.linked_task(&link).map(|t| t.id) {
+                    self.select_by_id(id);
+                    self.input_mode = InputMode::Normal;
+                }
+            }
+            _ => {
+                if !self.link_missing(&link) {
+                    if let Err(e) = App::open_external(&link.target) {
+                        self.notify(format!("could not open {}: {}", link.target, e), true);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn delete_entry(&mut self) {
+        match (self.state.selected(), self.detail_entry()) {
+            (Some(i), Some(DetailEntry::Link(l))) => {
+                self.items[i].links.remove(l);
+                self.clamp_detail_cursor();
+                self.write();
+            }
+            (Some(i), Some(DetailEntry::Comment(c))) => {
+                let id = self.items[i].comments[c].id;
+                self.items[i].delete_comment(id);
+                self.clamp_detail_cursor();
+                self.write();
+            }
+            _ => (),
+        }
+    }
+
+    pub fn select_by_id(&mut self, id: i64) {
+        if let Some(idx) = self.index_by_id(id) {
+            let topic = self.topic_of(idx);
+            self.collapsed.retain(|t| *t != topic);
+            self.scroll = 0;
+            self.select_row(Some(Row::Task(idx)));
+        }
+    }
+
+    /// Selects the next list row, wrapping around.
+    pub fn next(&mut self) {
+        let rows = self.rows();
+        let row = match self.selected_row() {
+            Some(r) => rows.get((r + 1) % rows.len()).cloned(),
+            None => rows.first().cloned(),
+        };
+        self.select_row(row);
+    }
+
+    /// Selects the previous list row, wrapping around.
+    pub fn previous(&mut self) {
+        let rows = self.rows();
+        let row = match self.selected_row() {
+            Some(r) => rows.get((r + rows.len() - 1) % rows.len()).cloned(),
+            None => rows.last().cloned(),
+        };
+        self.select_row(row);
+    }
+
+    pub fn toggle_grouping(&mut self) {
+        self.group_by_topic = !self.group_by_topic;
+        if let Some(topic) = self.selected_header.take() {
+            if let Some(i) = (0..self.items.len()).find(|i| self.items[*i].display && self.topic_of(*i) == topic) {
+                self.state.select(Some(i));
+            }
+        }
+        self.clamp_selection();
+    }
+
+    /// Collapses or expands the selected group; on a task it collapses the
+    /// task's group.
+    pub fn toggle_group(&mut self) {
+        if !self.group_by_topic {
+            return;
+        }
+        let topic = match (&self.selected_header, self.state.selected()) {
+            (Some(topic), _) => topic.clone(),
+            (None, Some(i)) => self.topic_of(i),
+            (None, None) => return,
+        };
+        match self.collapsed.iter().position(|t| *t == topic) {
+            Some(pos) => {
+                self.collapsed.remove(pos);
+            }
+            None => self.collapsed.push(topic.clone()),
+        }
+        self.select_row(Some(Row::Header(topic)));
+    }
+
+    /// Jumps to the next (or previous) topic header.
+    pub fn jump_group(&mut self, forward: bool) {
+        let rows = self.rows();
+        let headers: Vec<usize> = (0..rows.len())
+            .filter(|r| matches!(rows[*r], Row::Header(_)))
+            .collect();
+        let current = self.selected_row();
+        let target = if forward {
+            headers
+                .iter()
+                .find(|h| current.is_none_or(|c| **h > c))
+                .or(headers.first())
+        } else {
+            headers
+                .iter()
+                .rev()
+                .find(|h| current.is_none_or(|c| **h < c))
+                .or(headers.last())
+        };
+        if let Some(r) = target {
+            self.select_row(rows.get(*r).cloned());
+        }
+    }
+
+    /// Selects the list row drawn `line` lines below the top of the list.
+    pub fn click_row(&mut self, line: usize) {
+        let rows = self.rows();
+        if let Some(row) = rows.get(self.list_offset + line) {
+            self.scroll = 0;
+            self.select_row(Some(row.clone()));
+        }
+    }
+
+    /// Applies the filter drawn on line `line` of the filter popup.
+    pub fn click_filter(&mut self, line: usize) {
+        if let Some(filter) = Filter::iterator().nth(line) {
+            self.filter_items(filter);
+        }
+    }
+
+    /// Whether (`x`, `y`) is on the border between the list and the
+    /// details pane.
+    pub fn on_divider(&self, x: u16, y: u16) -> bool {
+        let (list, details) = (self.areas.list, self.areas.details);
+        match self.layout {
+            PageLayout::Vertical => {
+                (x + 1 == list.right() || x == details.x) && y >= list.y && y < list.bottom()
+            }
+            PageLayout::Horizontal => {
+                (y + 1 == list.bottom() || y == details.y) && x >= list.x && x < list.right()
+            }
+            _ => false,
+        }
+    }
+
+    /// Moves the divider to column `x` (vertical layout) or row `y`
+    /// (horizontal layout), keeping both panes at least a fifth wide.
+    pub fn drag_split(&mut self, x: u16, y: u16) {
+        let (list, details) = (self.areas.list, self.areas.details);
+        let (position, start, end) = match self.layout {
+            PageLayout::Vertical => (x, list.x, details.right()),
+            PageLayout::Horizontal => (y, list.y, details.bottom()),
+            _ => return,
+        };
+        if end > start {
+            let percent = (position.saturating_sub(start) + 1) as u32 * 100 / (end - start) as u32;
+            self.split = (percent as u16).clamp(20, 80);
+        }
+    }
+
+    /// Switches the page layout. The calendar filters the list to its
+    /// selected day, starting today; leaving it drops that filter.
+    pub fn set_layout(&mut self, layout: PageLayout) {
+        if layout == self.layout {
+            return;
+        }
+        self.layout = layout;
+        self.calendar_date = match layout {
+            PageLayout::Calendar => Some(day_start(current_timestamp())),
+            _ => None,
+        };
+        self.apply_filter();
+    }
+
+    pub fn toggle_board(&mut self) {
+        self.set_layout(match self.layout {
+            PageLayout::Board => PageLayout::Vertical,
+            _ => PageLayout::Board,
+        });
+    }
+
+    pub fn toggle_calendar(&mut self) {
+        self.set_layout(match self.layout {
+            PageLayout::Calendar => PageLayout::Vertical,
+            _ => PageLayout::Calendar,
+        });
+    }
+
+    /// Moves the calendar `days` days forward (or back for negative values).
+    pub fn calendar_step(&mut self, days: i64) {
+        if let Some(date) = self.calendar_date {
+            self.calendar_date = Some(date + days * DAY);
+            self.apply_filter();
+        }
+    }
+
+    pub fn calendar_today(&mut self) {
+        if self.calendar_date.is_some() {
+            self.calendar_date = Some(day_start(current_timestamp()));
+            self.apply_filter();
+        }
+    }
+
+    /// Unfinished tasks with a due date, split into overdue (due before
+    /// today), today, this week and later. Each section is sorted by due date.
+    pub fn agenda(&self, now: i64) -> Vec<(&'static str, Vec<usize>)> {
+        let today = day_start(now);
+        let next_week = week_start(now) + 7 * DAY;
+        let mut sections: Vec<(&'static str, Vec<usize>)> = vec![
+            ("Overdue", Vec::new()),
+            ("Today", Vec::new()),
+            ("This week", Vec::new()),
+            ("Later", Vec::new()),
+        ];
+        let mut dated: Vec<usize> = (0..self.items.len())
+            .filter(|i| self.items[*i].status != Status::Done && self.items[*i].due_timestamp.is_some())
+            .collect();
+        dated.sort_by_key(|i| self.items[*i].due_timestamp);
+        for i in dated {
+            let due = self.items[i].due_timestamp.unwrap_or(0);
+            let section = if due < today {
+                0
+            } else if due < today + DAY {
+                1
+            } else if due < next_week {
+                2
+            } else {
+                3
+            };
+            sections[section].1.push(i);
+        }
+        sections
+    }
+
+    /// Displayed tasks with the status of board column `column`.
+    pub fn board_cards(&self, column: usize) -> Vec<usize> {
+        (0..self.items.len())
+            .filter(|i| self.items[*i].display && self.items[*i].status == BOARD_COLUMNS[column])
+            .collect()
+    }
+
+    /// Column of the selected card; `board_column` only matters while the
+    /// selected column is empty.
+    pub fn active_column(&self) -> usize {
+        match self.state.selected() {
+            Some(i) => BOARD_COLUMNS
+                .iter()
+                .position(|s| *s == self.items[i].status)
+                .unwrap_or(0),
+            None => self.board_column,
+        }
+    }
+
+    /// Moves to the column left or right of the current one, keeping the
+    /// card position where possible.
+    pub fn board_column_step(&mut self, forward: bool) {
+        let column = self.active_column();
+        let target = match forward {
+            true if column + 1 < BOARD_COLUMNS.len() => column + 1,
+            false if column > 0 => column - 1,
+            _ => return,
+        };
+        let position = self
+            .state
+            .selected()
+            .and_then(|i| self.board_cards(column).iter().position(|c| *c == i))
+            .unwrap_or(0);
+        let cards = self.board_cards(target);
+        self.board_column = target;
+        match cards.get(position).or(cards.last()) {
+            Some(i) => self.select_by_id(self.items[*i].id),
+            None => self.select_row(None),
+        }
+    }
+
+    /// Selects the next (or previous) card of the current column, wrapping
+    /// around.
+    pub fn board_card_step(&mut self, forward: bool) {
+        let cards = self.board_cards(self.active_column());
+        if cards.is_empty() {
+            return;
+        }
+        let next = match self.state.selected().and_then(|i| cards.iter().position(|c| *c == i)) {
+            Some(p) if forward => (p + 1) % cards.len(),
+            Some(p) => (p + cards.len() - 1) % cards.len(),
+            None => 0,
+        };
+        self.select_by_id(self.items[cards[next]].id);
+    }
+
+    /// Moves the selected card to the next (or previous) column, which
+    /// changes its status.
+    pub fn move_card(&mut self, forward: bool) {
+        let i = match self.state.selected() {
+            Some(i) => i,
+            None => return,
+        };
+        let column = self.active_column();
+        let target = match forward {
+            true if column + 1 < BOARD_COLUMNS.len() => column + 1,
+            false if column > 0 => column - 1,
+            _ => return,
+        };
+        let id = self.items[i].id;
+        self.items[i].set_status(BOARD_COLUMNS[target]);
+        if let Some(parent_id) = self.items[i].parent_id {
+            self.roll_up(parent_id);
+        }
+        self.board_column = target;
+        self.apply_filter();
+        self.write();
+        self.select_by_id(id);
+    }
+
+    pub fn change_input_area(&mut self) {
+        match self.input_area {
+            InputArea::Topic => self.input_area = InputArea::Task,
+            InputArea::Task => self.input_area = InputArea::Description,
+            InputArea::Description => {
+                if !self.custom_fields.is_empty() {
+                    self.input_area = InputArea::Fields
+                } else {
+                    self.input_area = InputArea::Topic
+                }
+            }
+            InputArea::Fields => match self.input_mode {
+                InputMode::SubTask | InputMode::SubTaskModify => self.input_area = InputArea::Task,
+                _ => self.input_area = InputArea::Topic,
+            },
+            _ => (),
+        }
+    }
+
+    /// Reorders `items` by the active sort, keeping every sub task right
+    /// after its parent. The selected task stays selected.
+    fn sort_items(&mut self) {
+        let (key, descending) = match &self.sort {
+            Some(sort) => sort.clone(),
+            None => return,
+        };
+        let selected = self.state.selected().map(|i| self.items[i].id);
+        let fields = self.custom_fields.clone();
+        let mut groups: Vec<Vec<Task>> = Vec::new();
+        for task in self.items.drain(..) {
+            match groups.last_mut() {
+                Some(group) if task.is_sub_task && group[0].child_list.contains(&task.id) => {
+                    group.push(task)
+                }
+                _ => groups.push(vec![task]),
+            }
+        }
+        for group in groups.iter_mut() {
+            group[1..].sort_by(|a, b| key.compare(a, b, descending, &fields));
+        }
+        groups.sort_by(|a, b| key.compare(&a[0], &b[0], descending, &fields));
+        self.items = groups.into_iter().flatten().collect();
+        if let Some(id) = selected {
+            self.state.select(self.index_by_id(id));
+        }
+    }
+
+    /// Writes the displayed tasks (only the marked ones when there is a
+    /// selection) to `path`; `.json` files get JSON, anything else markdown.
+    pub fn export(&self, path: &str) -> Result<String, String> {
+        self.export_as(path, path.ends_with(".json"))
+    }
+
+    /// Like `export` with the format given; returns the written path.
+    fn export_as(&self, path: &str, json: bool) -> Result<String, String> {
+        let path = match path.strip_prefix("~/") {
+            Some(rest) => format!("{}/{}", App::home_dir(), rest),
+            None if path.is_empty() => {
+                format!("{}/export.{}", App::get_path(), if json { "json" } else { "md" })
+            }
+            None => path.to_string(),
+        };
+        let tasks: Vec<Task> = self
+            .items
+            .iter()
+            .filter(|t| t.display)
+            .filter(|t| self.marked.is_empty() || self.marked.contains(&t.id))
+            .cloned()
+            .collect();
+        let content = if json {
+            to_json(&tasks)
+        } else {
+            to_markdown(&tasks, &self.custom_fields)
+        };
+        fs::write(&path, content).map_err(|e| format!("{}: {}", path, e))?;
+        Ok(path)
+    }
+
+    pub fn toggle_mark(&mut self) {
+        if let Some(i) = self.state.selected() {
+            let id = self.items[i].id;
+            match self.marked.iter().position(|m| *m == id) {
+                Some(pos) => {
+                    self.marked.remove(pos);
+                }
+                None => self.marked.push(id),
+            }
+        }
+    }
+
+    pub fn start_visual(&mut self) {
+        if let Some(i) = self.state.selected() {
+            self.visual_anchor = Some(i);
+            self.input_mode = InputMode::Visual;
+            self.mark_range();
+        }
+    }
+
+    pub fn stop_visual(&mut self) {
+        self.visual_anchor = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Marks every displayed task between the visual anchor and the cursor.
+    pub fn mark_range(&mut self) {
+        let rows = self.visible_rows();
+        let position = |i: Option<usize>| i.and_then(|i| rows.iter().position(|r| *r == i));
+        if let (Some(anchor), Some(i)) = (position(self.visual_anchor), position(self.state.selected())) {
+            for r in rows[anchor.min(i)..=anchor.max(i)].iter() {
+                let id = self.items[*r].id;
+                if !self.marked.contains(&id) {
+                    self.marked.push(id);
+                }
+            }
+        }
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+    }
+
+    /// Ids a bulk operation works on: the marked tasks, or the selected one.
+    pub fn targets(&self) -> Vec<i64> {
+        if !self.marked.is_empty() {
+            return self.marked.clone();
+        }
+        match self.state.selected() {
+            Some(i) => vec![self.items[i].id],
+            None => Vec::new(),
+        }
+    }
+
+    pub fn open_bulk(&mut self) {
+        if !self.targets().is_empty() {
+            self.visual_anchor = None;
+            self.input_mode = InputMode::Bulk;
+        }
+    }
+
+    /// Applies `change` to every target and saves once, so the whole
+    /// operation is a single undo step.
+    fn bulk_update<F: Fn(&mut Task)>(&mut self, change: F) {
+        let targets = self.targets();
+        let mut parents = Vec::new();
+        for task in self.items.iter_mut().filter(|t| targets.contains(&t.id)) {
+            change(task);
+            if let Some(p) = task.parent_id {
+                parents.push(p);
+            }
+        }
+        for parent_id in parents {
+            self.roll_up(parent_id);
+        }
+        self.apply_filter();
+        self.get_child_list(self.state.selected());
+        self.write();
+    }
+
+    pub fn bulk_status(&mut self, status: Status) {
+        self.bulk_update(|t| t.set_status(status));
+        self.close_prompt();
+    }
+
+    pub fn bulk_delete(&mut self) {
+        for id in self.targets() {
+            if let Some(i) = self.index_by_id(id) {
+                self.remove_task(i);
+            }
+        }
+        self.marked.clear();
+        self.apply_filter();
+        self.write();
+        self.clamp_selection();
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Moves the targets under `parent_id`, or to the top level for `None`.
+    /// Tasks that have sub tasks of their own are left where they are.
+    fn bulk_move(&mut self, parent_id: Option<i64>) {
+        let topic = parent_id
+            .and_then(|p| self.index_by_id(p))
+            .map(|p| self.items[p].topic.clone());
+        let mut moved = Vec::new();
+        for id in self.targets() {
+            let i = match self.index_by_id(id) {
+                Some(i) => i,
+                None => continue,
+            };
+            if Some(id) == parent_id || !self.items[i].child_list.is_empty() {
+                continue;
+            }
+            let mut task = self.items.remove(i);
+            if let Some(old) = task.parent_id.and_then(|p| self.index_by_id(p)) {
+                self.items[old].child_list.retain(|c| *c != id);
+                let old_id = self.items[old].id;
+                self.roll_up(old_id);
+            }
+            moved.push(id);
+            task.parent_id = parent_id;
+            task.is_sub_task = parent_id.is_some();
+            match parent_id.and_then(|p| self.index_by_id(p)) {
+                Some(p) => {
+                    task.topic = topic.clone().unwrap_or_default();
+                    let position = p + 1 + self.items[p + 1..]
+                        .iter()
+                        .take_while(|t| t.parent_id == parent_id)
+                        .count();
+                    self.items[p].child_list.push(id);
+                    self.items.insert(position, task);
+                    self.roll_up(parent_id.unwrap());
+                }
+                None => self.items.push(task),
+            }
+        }
+        self.apply_filter();
+        self.write();
+        if let Some(first) = moved.first() {
+            self.select_by_id(*first);
+        }
+    }
+
+    /// Finds a task by `#<id>` or by a part of its name that only one task
+    /// matches.
+    fn find_task(&self, value: &str) -> Result<&Task, String> {
+        let needle = value.trim_start_matches('#');
+        let task = match needle.parse::<i64>() {
+            Ok(id) => self.items.iter().find(|t| t.id == id),
+            Err(_) => {
+                let needle = needle.to_lowercase();
+                let found: Vec<&Task> = self
+                    .items
+                    .iter()
+                    .filter(|t| t.name.to_lowercase().contains(&needle))
+                    .collect();
+                if found.len() > 1 {
+                    return Err(format!("'{}' matches {} tasks", value, found.len()));
+                }
+                found.first().copied()
+            }
+        };
+        task.ok_or(format!("no task matches '{}'", value))
+    }
+
+    /// Finds a task to move under by id or by a unique part of its name.
+    fn find_parent(&self, value: &str) -> Result<i64, String> {
+        let task = self.find_task(value)?;
+        if task.is_sub_task {
+            Err(format!("'{}' is a sub task", task.name))
+        } else if self.targets().contains(&task.id) {
+            Err(format!("'{}' is part of the selection", task.name))
+        } else {
+            Ok(task.id)
+        }
+    }
+
+    /// Moves done tasks (the marked ones, or the selected one) to the
+    /// archive together with their sub tasks.
+    pub fn archive_done(&mut self) {
+        let ids: Vec<i64> = self
+            .targets()
+            .into_iter()
+            .filter(|id| {
+                self.index_by_id(*id)
+                    .is_some_and(|i| self.items[i].status == Status::Done)
+            })
+            .collect();
+        self.archive_tasks(&ids);
+    }
+
+    /// Archives top level tasks that have been done, with all of their sub
+    /// tasks, for longer than `behavior.archive_after_days`.
+    fn auto_archive(&mut self) {
+        let days = match self.archive_after_days {
+            Some(d) => d,
+            None => return,
+        };
+        let cutoff = current_timestamp() - days * 24 * 60 * 60;
+        let ids: Vec<i64> = self
+            .items
+            .iter()
+            .filter(|t| !t.is_sub_task && t.status == Status::Done)
+            .filter(|t| t.status_change_timestamp.is_some_and(|c| c <= cutoff))
+            .filter(|t| {
+                self.items
+                    .iter()
+                    .filter(|c| t.child_list.contains(&c.id))
+                    .all(|c| c.status == Status::Done)
+            })
+            .map(|t| t.id)
+            .collect();
+        self.archive_tasks(&ids);
+    }
+
+    fn archive_tasks(&mut self, ids: &[i64]) {
+        if ids.is_empty() {
+            return;
+        }
+        // work on copies: the tasks only leave the list once the archive
+        // holding them is on disk
+        let now = current_timestamp();
+        let mut items = self.items.clone();
+        let mut archive = self.archive.clone();
+        let mut parents = Vec::new();
+        for id in ids {
+            let task = match items.iter().find(|t| t.id == *id) {
+                Some(t) => t.clone(),
+                None => continue,
+            };
+            if let Some(parent_id) = task.parent_id.filter(|_| task.is_sub_task) {
+                if let Some(parent) = items.iter_mut().find(|t| t.id == parent_id) {
+                    parent.child_list.retain(|c| c != id);
+                }
+                parents.push(parent_id);
+            }
+            let (moved, kept): (Vec<Task>, Vec<Task>) = items
+                .drain(..)
+                .partition(|t| t.id == task.id || task.child_list.contains(&t.id));
+            items = kept;
+            for mut archived in moved {
+                archived.set_archived(Some(now));
+                archive.push(archived);
+            }
+        }
+        let previous = std::mem::replace(&mut self.archive, archive);
+        if let Err(e) = self.write_archive() {
+            self.archive = previous;
+            self.notify(e, true);
+            return;
+        }
+        self.items = items;
+        for parent_id in parents {
+            self.roll_up(parent_id);
+        }
+        self.marked.retain(|id| self.items.iter().any(|t| t.id == *id));
+        self.refresh_archive();
+        self.apply_filter();
+        self.write();
+        self.clamp_selection();
+    }
+
+    pub fn open_archive(&mut self) {
+        self.refresh_archive();
+        self.archive_state.select(if self.archive_view.is_empty() { None } else { Some(0) });
+        self.input_mode = InputMode::Archive;
+    }
+
+    /// Selected archived task, as an index into `archive`.
+    pub fn archive_selected(&self) -> Option<usize> {
+        self.archive_state
+            .selected()
+            .and_then(|s| self.archive_view.get(s))
+            .copied()
+    }
+
+    pub fn search_archive(&mut self, query: String) {
+        self.archive_query = query;
+        self.refresh_archive();
+        self.archive_state.select(if self.archive_view.is_empty() { None } else { Some(0) });
+    }
+
+    fn refresh_archive(&mut self) {
+        self.archive_view = archive::search(&self.archive, &self.archive_query);
+        let selected = match self.archive_state.selected() {
+            _ if self.archive_view.is_empty() => None,
+            Some(i) => Some(i.min(self.archive_view.len() - 1)),
+            None => Some(0),
+        };
+        self.archive_state.select(selected);
+    }
+
+    pub fn archive_next(&mut self) {
+        if let Some(i) = self.archive_state.selected() {
+            self.archive_state.select(Some((i + 1) % self.archive_view.len()));
+        }
+    }
+
+    pub fn archive_previous(&mut self) {
+        if let Some(i) = self.archive_state.selected() {
+            let len = self.archive_view.len();
+            self.archive_state.select(Some((i + len - 1) % len));
+        }
+    }
+
+    /// Moves the selected archived task (and its archived sub tasks) back to
+    /// the active list. A sub task goes back under its parent when the parent
+    /// is still active, otherwise it is restored as a top level task.
+    pub fn restore(&mut self) {
+        let index = match self.archive_selected() {
+            Some(i) => i,
+            None => return,
+        };
+        let previous = self.archive.clone();
+        let mut task = self.archive.remove(index);
+        let (mut children, rest): (Vec<Task>, Vec<Task>) = self
+            .archive
+            .drain(..)
+            .partition(|t| task.child_list.contains(&t.id));
+        self.archive = rest;
+        // the tasks only return to the list once the archive without them
+        // is on disk
+        if let Err(e) = self.write_archive() {
+            self.archive = previous;
+            self.notify(e, true);
+            return;
+        }
+        children.sort_by_key(|c| task.child_list.iter().position(|id| *id == c.id));
+        task.child_list = children.iter().map(|c| c.id).collect();
+        task.set_archived(None);
+        for child in children.iter_mut() {
+            child.set_archived(None);
+        }
+        let id = task.id;
+        match task.parent_id.and_then(|p| self.index_by_id(p)) {
+            Some(p) if task.is_sub_task => {
+                let parent_id = self.items[p].id;
+                task.topic = self.items[p].topic.clone();
+                let position = p + 1 + self.items[p + 1..]
+                    .iter()
+                    .take_while(|t| t.parent_id == Some(parent_id))
+                    .count();
+                if !self.items[p].child_list.contains(&id) {
+                    self.items[p].child_list.push(id);
+                }
+                self.items.insert(position, task);
+                self.roll_up(parent_id);
+            }
+            _ => {
+                task.is_sub_task = false;
+                task.parent_id = None;
+                self.items.push(task);
+                self.items.extend(children);
+            }
+        }
+        self.refresh_archive();
+        self.apply_filter();
+        self.write();
+        self.select_by_id(id);
+    }
+
+    /// Applies a sort spec such as `due` or `-priority` (descending); custom
+    /// field names are accepted as well.
+    fn apply_sort(&mut self, spec: &str) -> Result<(), String> {
+        let spec = spec.trim();
+        let (name, descending) = match spec.strip_prefix('-') {
+            Some(n) => (n.trim(), true),
+            None => (spec, false),
+        };
+        let key = SortKey::parse(name, &self.custom_fields)?;
+        self.set_sort(Some((key, descending)));
+        Ok(())
+    }
+
+    /// Changes the order of the list only; the sort is not an edit, so it
+    /// is neither saved nor an undo step.
+    pub fn set_sort(&mut self, sort: Option<(SortKey, bool)>) {
+        self.sort = sort;
+        self.sort_items();
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Switches to the saved view at `index`: its query replaces the active
+    /// filters, then its sort order and layout are applied.
+    pub fn apply_view(&mut self, index: usize) {
+        let view = match self.views.get(index) {
+            Some(v) => v.clone(),
+            None => return,
+        };
+        if let Err(e) = self.set_query(&view.filter) {
+            self.view_error = Some(format!("view '{}': {}", view.name, e));
+            return;
+        }
+        self.filter = Filter::All;
+        self.field_filter = None;
+        self.view_error = None;
+        if let Some(sort) = &view.sort {
+            if let Err(e) = self.apply_sort(sort) {
+                self.view_error = Some(format!("view '{}': {}", view.name, e));
+            }
+        }
+        match view.layout.as_deref() {
+            Some("horizontal") => self.set_layout(PageLayout::Horizontal),
+            Some("vertical") => self.set_layout(PageLayout::Vertical),
+            Some("board") => self.set_layout(PageLayout::Board),
+            Some("calendar") => self.set_layout(PageLayout::Calendar),
+            _ => (),
+        }
+        self.view = Some(index);
+        self.apply_filter();
+        if let Some(first) = self.items.iter().find(|t| t.display) {
+            self.select_by_id(first.id);
+        }
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Leaves the active view and shows all tasks again.
+    pub fn clear_view(&mut self) {
+        self.view = None;
+        self.view_error = None;
+        self.query = None;
+        self.filter_items(Filter::All);
+    }
+
+    pub fn open_views(&mut self) {
+        if !self.views.is_empty() {
+            self.view_state.select(Some(self.view.unwrap_or(0)));
+            self.input_mode = InputMode::ViewPicker;
+        }
+    }
+
+    pub fn view_next(&mut self) {
+        if let Some(i) = self.view_state.selected() {
+            self.view_state.select(Some((i + 1) % self.views.len()));
+        }
+    }
+
+    pub fn view_previous(&mut self) {
+        if let Some(i) = self.view_state.selected() {
+            let len = self.views.len();
+            self.view_state.select(Some((i + len - 1) % len));
+        }
+    }
+
+    pub fn open_goto(&mut self) {
+        self.goto_search("");
+        self.input_mode = InputMode::GoTo;
+    }
+
+    /// Ranks every task in the store by how well "topic name" fuzzy matches
+    /// `query`, best first.
+    pub fn goto_search(&mut self, query: &str) {
+        let mut ranked: Vec<(i64, usize)> = (0..self.items.len())
+            .filter_map(|i| {
+                let t = &self.items[i];
+                fuzzy_match(query, &format!("{} {}", t.topic, t.name)).map(|(score, _)| (score, i))
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        self.goto_results = ranked.into_iter().map(|(_, i)| i).collect();
+        self.goto_state.select(if self.goto_results.is_empty() { None } else { Some(0) });
+    }
+
+    pub fn goto_selected(&self) -> Option<usize> {
+        self.goto_state
+            .selected()
+            .and_then(|s| self.goto_results.get(s))
+            .copied()
+    }
+
+    pub fn goto_next(&mut self) {
+        if let Some(i) = self.goto_state.selected() {
+            self.goto_state.select(Some((i + 1) % self.goto_results.len()));
+        }
+    }
+
+    pub fn goto_previous(&mut self) {
+        if let Some(i) = self.goto_state.selected() {
+            let len = self.goto_results.len();
+            self.goto_state.select(Some((i + len - 1) % len));
+        }
+    }
+
+    /// Selects the picked task. When the task is hidden, the search, query,
+    /// view and filters are cleared first (the calendar goes back to the
+    /// list, deferred tasks switch to the deferred filter) and its group is
+    /// unfolded.
+    pub fn goto(&mut self) {
+        let id = match self.goto_selected() {
+            Some(i) => self.items[i].id,
+            None => return,
+        };
+        self.input_mode = InputMode::Normal;
+        let hidden = |app: &App| app.index_by_id(id).is_none_or(|i| !app.items[i].display);
+        if hidden(self) {
+            self.search_query.clear();
+            self.query = None;
+            self.view = None;
+            self.field_filter = None;
+            self.filter = Filter::All;
+            if self.layout == PageLayout::Calendar {
+                self.layout = PageLayout::Vertical;
+                self.calendar_date = None;
+            }
+            self.apply_filter();
+        }
+        if hidden(self) {
+            self.filter = Filter::Deferred;
+            self.apply_filter();
+        }
+        if hidden(self) {
+            self.notify("task could not be shown".to_string(), true);
+            return;
+        }
+        self.select_by_id(id);
+    }
+
+    /// Opens the `:` command line, starting past the end of the history.
+    pub fn open_command(&mut self) {
+        self.prompt_error = None;
+        self.completions.clear();
+        self.history_position = None;
+        self.input_mode = InputMode::Command;
+    }
+
+    /// Runs a `:` command line. Errors keep the command line open with the
+    /// message, like the prompts do.
+    pub fn run_command(&mut self, line: &str) {
+        let line = line.trim();
+        if !line.is_empty() && self.command_history.last().map(String::as_str) != Some(line) {
+            self.command_history.push(line.to_string());
+        }
+        self.completions.clear();
+        let result = match Command::parse(line) {
+            Ok(command) => self.execute(command),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => self.close_prompt(),
+            Err(e) => self.prompt_error = Some(e),
+        }
+    }
+
+    fn execute(&mut self, command: Command) -> Result<(), String> {
+        match command {
+            Command::Add { topic, name } => {
+                // no topic falls back to the default topic of `Task::create`
+                let topic = Some(topic).filter(|t| !t.is_empty());
+                let mut task = Task::create(topic, name, Some(String::new()), None, None);
+                task.id = self.unique_id();
+                let id = task.id;
+                self.items.push(task);
+                self.write();
+                self.apply_filter();
+                self.select_by_id(id);
+            }
+            Command::Done => {
+                if self.targets().is_empty() {
+                    return Err("no task selected".to_string());
+                }
+                self.bulk_update(|t| t.set_status(Status::Done));
+            }
+            Command::Tag(tags) => {
+                if self.targets().is_empty() {
+                    return Err("no task selected".to_string());
+                }
+                self.bulk_update(|t| t.retag(&tags));
+            }
+            Command::Sort(spec) => {
+                match spec {
+                    Some(spec) => self.apply_sort(&spec)?,
+                    None => self.set_sort(None),
+                }
+                self.view = None;
+            }
+            Command::Filter(query) => {
+                self.set_query(&query)?;
+                self.view = None;
+            }
+            Command::Export { json, path } => {
+                let path = self.export_as(&path, json)?;
+                self.notify(format!("exported to {}", path), false);
+            }
+            Command::Write => {
+                self.save_file();
+                if self.save_error.is_none() {
+                    self.notify("Saved".to_string(), false);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Tab completion of the command line; the candidates are kept for the
+    /// footer when the word is ambiguous.
+    pub fn complete_command(&mut self, line: &str) -> String {
+        let fields: Vec<String> = self.custom_fields.iter().map(|f| f.name.clone()).collect();
+        let mut topics: Vec<String> = Vec::new();
+        let mut tags: Vec<String> = Vec::new();
+        for task in self.items.iter() {
+            if !task.topic.is_empty() && !topics.contains(&task.topic) {
+                topics.push(task.topic.clone());
+            }
+            for tag in task.tags.iter() {
+                if !tags.contains(tag) {
+                    tags.push(tag.clone());
+                }
+            }
+        }
+        let (line, candidates) = complete(line, &fields, &topics, &tags);
+        self.completions = candidates;
+        line
+    }
+
+    /// Steps through the command history, older with `back`. Returns the
+    /// line to show, empty past the newest entry.
+    pub fn history_step(&mut self, back: bool) -> Option<String> {
+        if self.command_history.is_empty() {
+            return None;
+        }
+        let last = self.command_history.len() - 1;
+        self.history_position = match (self.history_position, back) {
+            (None, true) => Some(last),
+            (None, false) => return None,
+            (Some(p), true) => Some(p.saturating_sub(1)),
+            (Some(p), false) if p < last => Some(p + 1),
+            (Some(_), false) => None,
+        };
+        Some(self.history_position.map_or(String::new(), |p| self.command_history[p].clone()))
+    }
+
+    /// Parses and applies a filter query; an empty query clears it.
+    pub fn set_query(&mut self, value: &str) -> Result<(), String> {
+        self.query = if value.trim().is_empty() {
+            None
+        } else {
+            let query = Query::parse(value, &self.custom_fields)?;
+            Some((value.trim().to_string(), query))
+        };
+        self.apply_filter();
+        Ok(())
+    }
+
+    /// Filters the list live while the search prompt is edited and keeps
+    /// the selection on a matching task.
+    pub fn search(&mut self, query: String) {
+        self.search_query = query;
+        self.apply_filter();
+        let hits = self.search_hits();
+        match self.state.selected() {
+            Some(i) if hits.contains(&i) => (),
+            _ => match hits.first() {
+                Some(first) => self.select_by_id(self.items[*first].id),
+                None => self.get_child_list(self.state.selected()),
+            },
+        }
+    }
+
+    pub fn clear_search(&mut self) {
+        if !self.search_query.is_empty() {
+            self.search(String::new());
+        }
+    }
+
+    /// Displayed tasks that match the search themselves (parents shown only
+    /// because of a matching sub task are not hits), in list order.
+    pub fn search_hits(&self) -> Vec<usize> {
+        if self.search_query.is_empty() {
+            return Vec::new();
+        }
+        let mut hits: Vec<usize> = (0..self.items.len())
+            .filter(|i| self.items[*i].display && self.items[*i].contains_text(&self.search_query))
+            .collect();
+        let order = self.list_order();
+        hits.sort_by_key(|i| order[*i]);
+        hits
+    }
+
+    /// Position of every task in the list, hidden tasks included, so tasks
+    /// in collapsed groups can be compared with visible ones.
+    fn list_order(&self) -> Vec<usize> {
+        let mut order = vec![0; self.items.len()];
+        if self.group_by_topic {
+            let mut topics: Vec<String> = Vec::new();
+            let mut keyed: Vec<(usize, usize)> = (0..self.items.len())
+                .map(|i| {
+                    let topic = self.topic_of(i);
+                    let group = match topics.iter().position(|t| *t == topic) {
+                        Some(g) => g,
+                        None => {
+                            topics.push(topic);
+                            topics.len() - 1
+                        }
+                    };
+                    (group, i)
+                })
+                .collect();
+            keyed.sort();
+            for (position, (_, i)) in keyed.into_iter().enumerate() {
+                order[i] = position;
+            }
+        } else {
+            for (i, o) in order.iter_mut().enumerate() {
+                *o = i;
+            }
+        }
+        order
+    }
+
+    fn jump_hit(&mut self, forward: bool) {
+        let hits = self.search_hits();
+        let order = self.list_order();
+        let current = self.state.selected().map(|i| order[i]);
+        let target = if forward {
+            hits.iter()
+                .find(|i| current.is_none_or(|c| order[**i] > c))
+                .or(hits.first())
+        } else {
+            hits.iter()
+                .rev()
+                .find(|i| current.is_none_or(|c| order[**i] < c))
+                .or(hits.last())
+        };
+        if let Some(i) = target {
+            self.select_by_id(self.items[*i].id);
+        }
+    }
+
+    pub fn next_hit(&mut self) {
+        self.jump_hit(true);
+    }
+
+    pub fn previous_hit(&mut self) {
+        self.jump_hit(false);
+    }
+
+    fn index_by_id(&self, id: i64) -> Option<usize> {
+        for (idx, task) in self.items.iter().enumerate() {
+            if task.id == id {
+                return Some(idx);
+            }
+        }
+        return None;
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.scroll = self.scroll.saturating_sub(1);
+        self.scroll_state = self.scroll_state.position(self.scroll)
     }
 
     pub fn scroll_down(&mut self) {
@@ -434,73 +2545,68 @@ impl App {
 
     pub fn filter_items(&mut self, new_filter: Filter) {
         self.filter = new_filter;
+        self.view = None;
         self.input_mode = InputMode::Normal;
-        let new_items: Vec<Task> = self
+        self.apply_filter();
+    }
+
+    /// Recomputes `display` for every task from the active filter. Deferred
+    /// tasks (and sub tasks of deferred parents) stay hidden until their date
+    /// passes, unless the deferred filter is active.
+    pub fn apply_filter(&mut self) {
+        let now = current_timestamp();
+        let deferred_ids: Vec<i64> = self
             .items
             .iter()
-            .map(|x| {
-                let mut task = x.clone();
-
-                match new_filter {
-                    Filter::New => {
-                        if task.status == Status::New {
-                            task.display = true
-                        } else {
-                            task.display = false
-                        }
-                    }
-                    Filter::Hold => {
-                        if task.status == Status::Hold {
-                            task.display = true
-                        } else {
-                            task.display = false
-                        }
-                    }
-                    Filter::InProgress => {
-                        if task.status == Status::InProgress {
-                            task.display = true
-                        } else {
-                            task.display = false
-                        }
-                    }
-                    Filter::Done => {
-                        if task.status == Status::Done {
-                            task.display = true
-                        } else {
-                            task.display = false
-                        }
-                    }
-                    Filter::NotDone => {
-                        if task.status != Status::Done {
-                            task.display = true
-                        } else {
-                            task.display = false
-                        }
-                    }
-                    Filter::All => task.display = true,
-                }
-                task
-            })
-            .map(|mut x| {
-                if x.child_list.len() > 0 {
-                    let child_list = x.child_list.clone();
-                    for child_id in child_list {
-                        let child_index = self.index_by_id(child_id);
-                        match child_index {
-                            Some(idx) => {
-                                let sub_task_display = self.items.get(idx).unwrap().display;
-                                if x.display == false && sub_task_display == true {
-                                    x.display = true;
-                                }
-                            }
-                            None => (),
-                        }
-                    }
-                }
-                x
-            })
+            .filter(|t| t.is_deferred(now))
+            .map(|t| t.id)
+            .collect();
+        let filter = self.filter;
+        let user_name = self.user_name.clone();
+        let query = self.search_query.clone();
+        let query_filter = self.query.as_ref().map(|(_, q)| q.clone());
+        let calendar_date = self.calendar_date;
+        for task in self.items.iter_mut() {
+            let deferred = deferred_ids.contains(&task.id)
+                || task.parent_id.is_some_and(|p| deferred_ids.contains(&p));
+            task.display = match filter {
+                Filter::New => task.status == Status::New && !deferred,
+                Filter::Hold => task.status == Status::Hold && !deferred,
+                Filter::InProgress => task.status == Status::InProgress && !deferred,
+                Filter::Done => task.status == Status::Done && !deferred,
+                Filter::NotDone => task.status != Status::Done && !deferred,
+                Filter::Deferred => deferred,
+                Filter::Mine => task.involves(&user_name) && !deferred,
+                Filter::All => !deferred,
+            };
+            if let Some((name, value)) = &self.field_filter {
+                let matches = task
+                    .fields
+                    .get(name)
+                    .is_some_and(|v| v.eq_ignore_ascii_case(value));
+                task.display = task.display && matches;
+            }
+            task.display = task.display && task.contains_text(&query);
+            if let Some(q) = &query_filter {
+                task.display = task.display && q.matches(task, now);
+            }
+            if let Some(day) = calendar_date {
+                task.display = task.display && task.on_day(day);
+            }
+        }
+        let displayed: Vec<i64> = self
+            .items
+            .iter()
+            .filter(|t| t.display)
+            .map(|t| t.id)
             .collect();
-        self.items = new_items;
+        for task in self.items.iter_mut() {
+            if !task.display && task.child_list.iter().any(|id| displayed.contains(id)) {
+                task.display = true;
+            }
+        }
+        self.last_tick = now;
+        self.clamp_selection();
     }
 }
 
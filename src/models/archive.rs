@@ -0,0 +1,40 @@
+use std::{fs, io::ErrorKind};
+
+use super::{os::FileSystem, task::Task};
+
+/// Done tasks moved out of the active list, stored in `~/.todo/archive.json`.
+pub struct Archive;
+
+impl FileSystem for Archive {}
+
+impl Archive {
+    fn file_path() -> String {
+        format!("{}/archive.json", Archive::get_path())
+    }
+
+    /// Reads the archive; a missing file is an empty archive, an unreadable
+    /// or corrupt one is an error.
+    pub fn read() -> Result<Vec<Task>, String> {
+        let path = Archive::file_path();
+        match fs::read_to_string(&path) {
+            Ok(s) => serde_json::from_str::<Vec<Task>>(&s).map_err(|e| format!("{}: {}", path, e)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(format!("{}: {}", path, e)),
+        }
+    }
+
+    pub fn write(tasks: &[Task]) -> Result<(), String> {
+        let path = Archive::file_path();
+        fs::write(&path, serde_json::to_string_pretty(tasks).unwrap())
+            .map_err(|e| format!("could not save {}: {}", path, e))
+    }
+}
+
+/// Indices of the archived tasks matching `query`, newest archive first.
+pub fn search(tasks: &[Task], query: &str) -> Vec<usize> {
+    let mut found: Vec<usize> = (0..tasks.len())
+        .filter(|i| tasks[*i].contains_text(query))
+        .collect();
+    found.sort_by_key(|i| std::cmp::Reverse(tasks[*i].archived_timestamp.unwrap_or(0)));
+    found
+}
@@ -0,0 +1,215 @@
+/// Commands of the `:` command line with their usage.
+pub const COMMANDS: &[(&str, &str)] = &[
+    ("add", "add topic/name"),
+    ("done", "done"),
+    ("tag", "tag +tag -tag"),
+    ("sort", "sort [-]key"),
+    ("filter", "filter QUERY"),
+    ("export", "export md|json [PATH]"),
+    ("w", "w"),
+];
+
+const SORT_KEYS: [&str; 7] = ["created", "updated", "status", "topic", "name", "priority", "due"];
+const QUERY_KEYS: [&str; 7] = ["status:", "topic:", "tag:", "priority:", "assignee:", "text:", "due<"];
+const STATUS_VALUES: [&str; 4] = ["new", "progress", "hold", "done"];
+
+/// A parsed command line such as `add backend/fix login` or `sort -due`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Add { topic: String, name: String },
+    Done,
+    Tag(String),
+    Sort(Option<String>),
+    Filter(String),
+    Export { json: bool, path: String },
+    Write,
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Result<Command, String> {
+        let line = line.trim().trim_start_matches(':');
+        let (name, args) = match line.split_once(char::is_whitespace) {
+            Some((name, args)) => (name, args.trim()),
+            None => (line, ""),
+        };
+        let usage = |name: &str| {
+            let usage = COMMANDS.iter().find(|(n, _)| *n == name).map_or("", |(_, u)| *u);
+            format!("usage: {}", usage)
+        };
+        match name {
+            "add" => match args.split_once('/') {
+                Some((topic, name)) if !name.trim().is_empty() => Ok(Command::Add {
+                    topic: topic.trim().to_string(),
+                    name: name.trim().to_string(),
+                }),
+                None if !args.is_empty() => Ok(Command::Add {
+                    topic: String::new(),
+                    name: args.to_string(),
+                }),
+                _ => Err(usage(name)),
+            },
+            "done" => Ok(Command::Done),
+            "tag" if !args.is_empty() => Ok(Command::Tag(args.to_string())),
+            "sort" => Ok(Command::Sort(Some(args.to_string()).filter(|a| !a.is_empty()))),
+            "filter" => Ok(Command::Filter(args.to_string())),
+            "export" => {
+                let (format, path) = match args.split_once(char::is_whitespace) {
+                    Some((format, path)) => (format, path.trim()),
+                    None => (args, ""),
+                };
+                match format {
+                    "md" | "markdown" | "" => Ok(Command::Export { json: false, path: path.to_string() }),
+                    "json" => Ok(Command::Export { json: true, path: path.to_string() }),
+                    _ => Err(usage(name)),
+                }
+            }
+            "w" | "write" => Ok(Command::Write),
+            "" => Err("empty command".to_string()),
+            _ if COMMANDS.iter().any(|(n, _)| *n == name) => Err(usage(name)),
+            _ => Err(format!("unknown command '{}'", name)),
+        }
+    }
+}
+
+/// Completes the last word of `line`. Returns the completed line and the
+/// candidates when more than one is left.
+///
+/// `fields`, `topics` and `tags` are the custom field names, topics and tags
+/// of the list, offered for `sort`, `add` and `tag` respectively.
+pub fn complete(line: &str, fields: &[String], topics: &[String], tags: &[String]) -> (String, Vec<String>) {
+    let start = line.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+    let (head, word) = line.split_at(start);
+    let command = head.split_whitespace().next();
+    let argument = head.split_whitespace().count();
+
+    // a prefix of the word that is kept as is, e.g. the `-` of `sort -due`
+    let (prefix, word) = match command {
+        Some("sort") => word.split_at(word.len() - word.trim_start_matches('-').len()),
+        Some("tag") => word.split_at(word.len() - word.trim_start_matches(['+', '-']).len()),
+        Some("filter") => match word.split_once(':') {
+            Some(("status", value)) => {
+                let value = value.rsplit(',').next().unwrap_or("");
+                word.split_at(word.len() - value.len())
+            }
+            _ => word.split_at(word.len() - word.trim_start_matches('-').len()),
+        },
+        _ => ("", word),
+    };
+
+    let options: Vec<String> = match (command, argument) {
+        (None, _) => COMMANDS.iter().map(|(n, _)| format!("{} ", n)).collect(),
+        (Some("sort"), 1) => SORT_KEYS
+            .iter()
+            .map(|s| s.to_string())
+            .chain(fields.iter().cloned())
+            .collect(),
+        (Some("export"), 1) => vec!["md ".to_string(), "json ".to_string()],
+        (Some("add"), 1) => topics.iter().map(|t| format!("{}/", t)).collect(),
+        (Some("tag"), _) => tags.iter().map(|t| format!("{} ", t)).collect(),
+        (Some("filter"), _) if prefix.contains(':') => STATUS_VALUES.iter().map(|s| format!("{} ", s)).collect(),
+        (Some("filter"), _) => QUERY_KEYS.iter().map(|s| s.to_string()).collect(),
+        _ => Vec::new(),
+    };
+    let candidates: Vec<String> = options
+        .into_iter()
+        .filter(|o| o.starts_with(word))
+        .collect();
+    let completed = match candidates.as_slice() {
+        [] => word.to_string(),
+        [only] => only.clone(),
+        [first, rest @ ..] => {
+            let mut common = first.clone();
+            for other in rest {
+                while !other.starts_with(common.as_str()) {
+                    common.pop();
+                }
+            }
+            common
+        }
+    };
+    let line = format!("{}{}{}", head, prefix, completed);
+    if candidates.len() > 1 {
+        (line, candidates.iter().map(|c| c.trim_end().to_string()).collect())
+    } else {
+        (line, Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn complete_line(line: &str) -> (String, Vec<String>) {
+        complete(line, &["ticket".to_string()], &["backend".to_string(), "bugs".to_string()], &[])
+    }
+
+    #[test]
+    fn parses_commands_and_arguments() {
+        assert_eq!(
+            Command::parse("add backend / fix login"),
+            Ok(Command::Add { topic: "backend".to_string(), name: "fix login".to_string() })
+        );
+        assert_eq!(
+            Command::parse("add fix login"),
+            Ok(Command::Add { topic: String::new(), name: "fix login".to_string() })
+        );
+        assert_eq!(Command::parse(":w"), Ok(Command::Write));
+        assert_eq!(Command::parse("  write "), Ok(Command::Write));
+        assert_eq!(Command::parse("sort"), Ok(Command::Sort(None)));
+        assert_eq!(Command::parse("sort -due"), Ok(Command::Sort(Some("-due".to_string()))));
+        assert_eq!(Command::parse("tag +a -b"), Ok(Command::Tag("+a -b".to_string())));
+        assert_eq!(
+            Command::parse("export json /tmp/out.json"),
+            Ok(Command::Export { json: true, path: "/tmp/out.json".to_string() })
+        );
+        assert_eq!(Command::parse("export"), Ok(Command::Export { json: false, path: String::new() }));
+    }
+
+    #[test]
+    fn bad_arguments_show_the_usage() {
+        assert_eq!(Command::parse("add"), Err("usage: add topic/name".to_string()));
+        assert_eq!(Command::parse("add backend/ "), Err("usage: add topic/name".to_string()));
+        assert_eq!(Command::parse("tag"), Err("usage: tag +tag -tag".to_string()));
+        assert_eq!(Command::parse("export csv"), Err("usage: export md|json [PATH]".to_string()));
+    }
+
+    #[test]
+    fn empty_and_unknown_commands_are_errors() {
+        assert_eq!(Command::parse(""), Err("empty command".to_string()));
+        assert_eq!(Command::parse(" : "), Err("empty command".to_string()));
+        assert_eq!(Command::parse("frobnicate"), Err("unknown command 'frobnicate'".to_string()));
+        // prefixes are completed with Tab, not run
+        assert_eq!(Command::parse("ex"), Err("unknown command 'ex'".to_string()));
+    }
+
+    #[test]
+    fn completes_a_unique_prefix() {
+        assert_eq!(complete_line("ex"), ("export ".to_string(), Vec::new()));
+        assert_eq!(complete_line("sort -pr"), ("sort -priority".to_string(), Vec::new()));
+        assert_eq!(complete_line("filter s"), ("filter status:".to_string(), Vec::new()));
+        assert_eq!(complete_line("filter status:new,p"), ("filter status:new,progress ".to_string(), Vec::new()));
+        assert_eq!(complete_line("add ba"), ("add backend/".to_string(), Vec::new()));
+    }
+
+    #[test]
+    fn ambiguous_prefixes_complete_the_common_part() {
+        assert_eq!(
+            complete_line("sort t"),
+            ("sort t".to_string(), vec!["topic".to_string(), "ticket".to_string()])
+        );
+        assert_eq!(
+            complete_line("add b"),
+            ("add b".to_string(), vec!["backend/".to_string(), "bugs/".to_string()])
+        );
+        let (line, candidates) = complete_line("");
+        assert_eq!(line, "");
+        assert_eq!(candidates.len(), COMMANDS.len());
+    }
+
+    #[test]
+    fn nothing_to_complete_keeps_the_line() {
+        assert_eq!(complete_line("frob"), ("frob".to_string(), Vec::new()));
+        assert_eq!(complete_line("done x"), ("done x".to_string(), Vec::new()));
+        assert_eq!(complete_line("sort zz"), ("sort zz".to_string(), Vec::new()));
+    }
+}
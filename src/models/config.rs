@@ -1,7 +1,11 @@
 use config::{Config, ConfigError, File};
 use serde::Deserialize;
+use std::{cmp::Ordering, collections::BTreeMap};
 
-use super::os::FileSystem;
+use super::{
+    os::FileSystem,
+    time::{parse_date, to_human_date},
+};
 
 #[derive(Debug, Deserialize)]
 pub struct Icons {
@@ -14,6 +18,12 @@ pub struct Icons {
     pub topic_icon_right: String,
     pub sub_task_middle: String,
     pub sub_task_end: String,
+    #[serde(default = "default_marked_icon")]
+    pub marked: String,
+}
+
+fn default_marked_icon() -> String {
+    "● ".to_string()
 }
 
 #[derive(Debug, Deserialize)]
@@ -49,11 +59,178 @@ pub struct Colors {
     pub icon_done_color: String,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Behavior {
+    pub status_rollup: String,
+    pub archive_after_days: Option<i64>,
+}
+
+impl Default for Behavior {
+    fn default() -> Self {
+        Self {
+            status_rollup: "manual".to_string(),
+            archive_after_days: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct User {
+    pub name: String,
+}
+
+impl Default for User {
+    fn default() -> Self {
+        Self {
+            name: std::env::var("USER").unwrap_or_default(),
+        }
+    }
+}
+
+/// A named combination of filter query, sort order and layout declared
+/// under `views:`; the first nine are bound to the number keys.
+#[derive(Debug, Deserialize, Clone)]
+pub struct View {
+    pub name: String,
+    #[serde(default)]
+    pub filter: String,
+    #[serde(default)]
+    pub sort: Option<String>,
+    #[serde(default)]
+    pub layout: Option<String>,
+}
+
+/// A user defined task field declared under `custom_fields:`. `kind` is one
+/// of `text`, `number`, `date` or `enum` (restricted to `values`).
+#[derive(Debug, Deserialize, Clone)]
+pub struct CustomField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub values: Vec<String>,
+}
+
+impl CustomField {
+    /// Checks the declared `type:` when the config is loaded.
+    pub fn check(&self) -> Result<(), String> {
+        match self.kind.as_str() {
+            "text" | "number" | "date" | "enum" => Ok(()),
+            other => Err(format!(
+                "custom field '{}': unknown type '{}' (text, number, date or enum)",
+                self.name, other
+            )),
+        }
+    }
+
+    /// Checks `value` against the field type and returns it normalized.
+    pub fn validate(&self, value: &str) -> Result<String, String> {
+        match self.kind.as_str() {
+            "number" => match value.parse::<f64>() {
+                Ok(_) => Ok(value.to_string()),
+                Err(_) => Err(format!("{} must be a number", self.name)),
+            },
+            "date" => match parse_date(value) {
+                Some(t) => Ok(to_human_date(t)[..10].to_string()),
+                None => Err(format!("{} must be a date (dd-mm-yyyy)", self.name)),
+            },
+            "enum" => match self.values.iter().find(|v| v.eq_ignore_ascii_case(value)) {
+                Some(v) => Ok(v.clone()),
+                None => Err(format!(
+                    "{} must be one of {}",
+                    self.name,
+                    self.values.join(", ")
+                )),
+            },
+            _ => Ok(value.to_string()),
+        }
+    }
+
+    /// Orders two stored values by the field type; enums follow the declared
+    /// order of their values.
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        match self.kind.as_str() {
+            "number" => {
+                let a = a.parse::<f64>().unwrap_or(f64::MIN);
+                let b = b.parse::<f64>().unwrap_or(f64::MIN);
+                a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+            }
+            "date" => parse_date(a).cmp(&parse_date(b)),
+            "enum" => {
+                let a = self.values.iter().position(|v| v == a);
+                let b = self.values.iter().position(|v| v == b);
+                a.cmp(&b)
+            }
+            _ => a.to_lowercase().cmp(&b.to_lowercase()),
+        }
+    }
+}
+
+/// Key overrides under `keys:`, one map per context from an action name to
+/// one or more key sequences, e.g. `quit: [q, "C-c"]` or `go_to: "g g"`.
+#[derive(Debug, Deserialize, Default)]
+pub struct Keys {
+    #[serde(default)]
+    pub normal: BTreeMap<String, KeyBinding>,
+    #[serde(default)]
+    pub search: BTreeMap<String, KeyBinding>,
+    #[serde(default)]
+    pub board: BTreeMap<String, KeyBinding>,
+    #[serde(default)]
+    pub calendar: BTreeMap<String, KeyBinding>,
+    #[serde(default)]
+    pub details: BTreeMap<String, KeyBinding>,
+    #[serde(default)]
+    pub filter: BTreeMap<String, KeyBinding>,
+    #[serde(default)]
+    pub bulk: BTreeMap<String, KeyBinding>,
+    #[serde(default)]
+    pub visual: BTreeMap<String, KeyBinding>,
+    #[serde(default)]
+    pub archive: BTreeMap<String, KeyBinding>,
+    #[serde(default)]
+    pub popup: BTreeMap<String, KeyBinding>,
+    #[serde(default)]
+    pub sort: BTreeMap<String, KeyBinding>,
+    #[serde(default)]
+    pub views: BTreeMap<String, KeyBinding>,
+    #[serde(default)]
+    pub templates: BTreeMap<String, KeyBinding>,
+    #[serde(default)]
+    pub stats: BTreeMap<String, KeyBinding>,
+    #[serde(default)]
+    pub help: BTreeMap<String, KeyBinding>,
+    #[serde(default)]
+    pub go_to: BTreeMap<String, KeyBinding>,
+    #[serde(default)]
+    pub command: BTreeMap<String, KeyBinding>,
+    #[serde(default)]
+    pub prompt: BTreeMap<String, KeyBinding>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum KeyBinding {
+    One(String),
+    Many(Vec<String>),
+}
+
 #[derive(Debug, Deserialize)]
 pub struct AppConfig {
     pub icons: Icons,
     pub colors: Colors,
     pub object_type: ObjectType,
+    #[serde(default)]
+    pub behavior: Behavior,
+    #[serde(default)]
+    pub custom_fields: Vec<CustomField>,
+    #[serde(default)]
+    pub user: User,
+    #[serde(default)]
+    pub views: Vec<View>,
+    #[serde(default)]
+    pub keys: Keys,
 }
 
 impl Default for AppConfig {
@@ -69,6 +246,7 @@ impl Default for AppConfig {
                 topic_icon_right: "".to_string(),
                 sub_task_middle: "|-".to_string(),
                 sub_task_end: "|-".to_string(),
+                marked: default_marked_icon(),
             },
             colors: Colors {
                 bat_color_sheme: "base16-256".to_string(),
@@ -95,6 +273,11 @@ impl Default for AppConfig {
             object_type: ObjectType {
                 border_type: "single".to_string(),
             },
+            behavior: Behavior::default(),
+            custom_fields: Vec::new(),
+            user: User::default(),
+            views: Vec::new(),
+            keys: Keys::default(),
         }
     }
 }
@@ -102,7 +285,7 @@ impl Default for AppConfig {
 impl AppConfig {
     fn read_config() -> Result<Config, ConfigError> {
         let mut cfg = Config::default();
-        let path = format!("{}/.config/todo/config.yaml", AppConfig::home_dir());
+        let path = format!("{}/config.yaml", AppConfig::config_path());
         cfg.merge(File::with_name(&path))?;
         Ok(cfg)
     }
@@ -0,0 +1,88 @@
+use super::{
+    config::CustomField,
+    task::{Status, Task},
+};
+
+/// Renders tasks as a markdown document. Sub tasks are listed under their
+/// parent when the parent is part of `tasks`.
+pub fn to_markdown(tasks: &[Task], fields: &[CustomField]) -> String {
+    let mut out = String::from("# Tasks\n");
+    for task in tasks
+        .iter()
+        .filter(|t| !t.is_sub_task || !tasks.iter().any(|p| Some(p.id) == t.parent_id))
+    {
+        out.push_str(&format!("\n## [{}] {}: {}\n\n", task.status, task.topic, task.name));
+        out.push_str(&format!("- created: {}\n", task.creation_date));
+        if let Some(date) = &task.status_change_date {
+            out.push_str(&format!("- updated: {}\n", date));
+        }
+        if let Some(duration) = &task.duration {
+            out.push_str(&format!("- duration: {}\n", duration));
+        }
+        if let Some(priority) = task.priority {
+            out.push_str(&format!("- priority: {}\n", priority));
+        }
+        if !task.tags.is_empty() {
+            let tags: Vec<String> = task.tags.iter().map(|t| format!("#{}", t)).collect();
+            out.push_str(&format!("- tags: {}\n", tags.join(" ")));
+        }
+        for field in fields {
+            if let Some(value) = task.fields.get(&field.name) {
+                out.push_str(&format!("- {}: {}\n", field.name, value));
+            }
+        }
+        for link in task.links.iter() {
+            out.push_str(&format!("- {}: {}\n", link.kind, link.target));
+        }
+        if !task.description.is_empty() {
+            out.push_str(&format!("\n{}\n", task.description));
+        }
+        let children: Vec<&Task> = tasks
+            .iter()
+            .filter(|t| task.child_list.contains(&t.id))
+            .collect();
+        if !children.is_empty() {
+            out.push_str("\n### Sub tasks\n\n");
+            for child in children {
+                let mark = if child.status == Status::Done { "x" } else { " " };
+                out.push_str(&format!("- [{}] {} ({})\n", mark, child.name, child.status));
+            }
+        }
+        if !task.comments.is_empty() {
+            out.push_str("\n### Comments\n");
+            for comment in task.comments.iter() {
+                out.push_str(&format!("\n**#{}** {}\n\n", comment.id, comment.date));
+                for line in comment.text.lines() {
+                    out.push_str(&format!("> {}\n", line));
+                }
+            }
+        }
+    }
+    out
+}
+
+pub fn to_json(tasks: &[Task]) -> String {
+    serde_json::to_string_pretty(tasks).unwrap()
+}
+
+/// One line per task for printing on the command line; sub tasks are
+/// indented under their parent when the parent is part of `tasks`.
+pub fn to_text(tasks: &[Task]) -> String {
+    let mut out = String::new();
+    for task in tasks.iter() {
+        let nested = task.is_sub_task && tasks.iter().any(|p| Some(p.id) == task.parent_id);
+        let mut line = if nested {
+            format!("    {:<11} {}", format!("[{}]", task.status), task.name)
+        } else {
+            format!("{:<11} {}: {}", format!("[{}]", task.status), task.topic, task.name)
+        };
+        for tag in task.tags.iter() {
+            line.push_str(&format!(" #{}", tag));
+        }
+        if let Some(date) = &task.due_date {
+            line.push_str(&format!(" (due {})", date));
+        }
+        out.push_str(&format!("{:<14} {}\n", task.id, line));
+    }
+    out
+}
@@ -0,0 +1,96 @@
+/// Fuzzy matches `pattern` against `text`: every pattern character (spaces
+/// ignored) has to appear in order, case-insensitive. Returns a score, higher
+/// is better, and the char positions of the match in `text`.
+///
+/// Contiguous runs and matches at the start of a word score higher, gaps and
+/// long texts score lower.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let pattern: Vec<char> = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if pattern.is_empty() {
+        return Some((0, Vec::new()));
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+
+    let mut best: Option<(i64, Vec<usize>)> = None;
+    // try every start of the first character so that a later, tighter
+    // match wins over the leftmost one
+    for start in (0..lower.len()).filter(|i| lower[*i] == pattern[0]) {
+        let mut positions = vec![start];
+        for p in pattern.iter().skip(1) {
+            let from = positions[positions.len() - 1] + 1;
+            match (from..lower.len()).find(|i| lower[*i] == *p) {
+                Some(at) => positions.push(at),
+                None => break,
+            }
+        }
+        if positions.len() < pattern.len() {
+            break;
+        }
+        let score = score(&chars, &positions);
+        if best.as_ref().is_none_or(|(s, _)| score > *s) {
+            best = Some((score, positions));
+        }
+    }
+    best
+}
+
+fn score(chars: &[char], positions: &[usize]) -> i64 {
+    let mut score = 0;
+    for (n, at) in positions.iter().enumerate() {
+        score += 1;
+        if *at == 0 || !chars[at - 1].is_alphanumeric() {
+            score += 8;
+        }
+        if n > 0 {
+            let gap = at - positions[n - 1] - 1;
+            if gap == 0 {
+                score += 5;
+            } else {
+                score -= gap.min(5) as i64;
+            }
+        }
+    }
+    score - (chars.len() / 10) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn characters_match_in_order_ignoring_case_and_spaces() {
+        assert_eq!(fuzzy_match("lgn", "Login page").map(|m| m.1), Some(vec![0, 2, 4]));
+        assert_eq!(fuzzy_match("b log", "backend login").map(|m| m.1), Some(vec![0, 8, 9, 10]));
+        assert!(fuzzy_match("gl", "login").is_none());
+        assert!(fuzzy_match("loginx", "login").is_none());
+    }
+
+    #[test]
+    fn empty_pattern_matches_everything() {
+        assert_eq!(fuzzy_match("", "anything"), Some((0, Vec::new())));
+        assert_eq!(fuzzy_match("  ", ""), Some((0, Vec::new())));
+        assert!(fuzzy_match("a", "").is_none());
+    }
+
+    #[test]
+    fn tighter_and_word_start_matches_score_higher() {
+        let score = |p: &str, t: &str| fuzzy_match(p, t).expect("match").0;
+        assert!(score("log", "login") > score("log", "a long dialog"));
+        assert!(score("dm", "dark mode") > score("dm", "random"));
+        // a later contiguous run wins over the leftmost scattered one
+        assert_eq!(fuzzy_match("ab", "a x ab").map(|m| m.1), Some(vec![4, 5]));
+    }
+
+    #[test]
+    fn positions_are_char_indices() {
+        assert_eq!(fuzzy_match("é", "café").map(|m| m.1), Some(vec![3]));
+    }
+}
@@ -0,0 +1,697 @@
+use std::{collections::BTreeMap, fmt};
+
+use tui_textarea::{Input, Key};
+
+use super::{
+    app::Filter,
+    config::{KeyBinding, Keys},
+    task::Status,
+};
+
+/// Where a binding applies. The search, board and calendar contexts take
+/// precedence over `Normal` while they are active, the others belong to a
+/// mode of their own: `Popup` covers the task and comment editors, `Prompt`
+/// the one-line inputs (search, go to and the command line fall back to it).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Context {
+    Normal,
+    Search,
+    Board,
+    Calendar,
+    Details,
+    Filter,
+    Bulk,
+    Visual,
+    Archive,
+    Popup,
+    Sort,
+    Views,
+    Templates,
+    Stats,
+    Help,
+    GoTo,
+    Command,
+    Prompt,
+}
+
+impl fmt::Display for Context {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Context::Normal => write!(f, "normal"),
+            Context::Search => write!(f, "search"),
+            Context::Board => write!(f, "board"),
+            Context::Calendar => write!(f, "calendar"),
+            Context::Details => write!(f, "details"),
+            Context::Filter => write!(f, "filter"),
+            Context::Bulk => write!(f, "bulk"),
+            Context::Visual => write!(f, "visual"),
+            Context::Archive => write!(f, "archive"),
+            Context::Popup => write!(f, "popup"),
+            Context::Sort => write!(f, "sort"),
+            Context::Views => write!(f, "views"),
+            Context::Templates => write!(f, "templates"),
+            Context::Stats => write!(f, "stats"),
+            Context::Help => write!(f, "help"),
+            Context::GoTo => write!(f, "go_to"),
+            Context::Command => write!(f, "command"),
+            Context::Prompt => write!(f, "prompt"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Help,
+    NewTask,
+    NewSubTask,
+    EditTask,
+    ChangeStatus,
+    DeleteTask,
+    AddComment,
+    EditLastComment,
+    DeleteLastComment,
+    Defer,
+    Assign,
+    AddLink,
+    Templates,
+    Duplicate,
+    DuplicateTree,
+    Next,
+    Previous,
+    ScrollDown,
+    ScrollUp,
+    FocusDetails,
+    Filter,
+    Sort,
+    Export,
+    Search,
+    GoTo,
+    Command,
+    Mark,
+    Visual,
+    Bulk,
+    Undo,
+    ArchiveDone,
+    Archive,
+    Views,
+    ClearView,
+    View(usize),
+    ToggleGrouping,
+    ToggleGroup,
+    NextGroup,
+    PreviousGroup,
+    HorizontalLayout,
+    VerticalLayout,
+    Board,
+    Calendar,
+    Stats,
+    Clear,
+    NextHit,
+    PreviousHit,
+    ColumnLeft,
+    ColumnRight,
+    CardDown,
+    CardUp,
+    MoveCardLeft,
+    MoveCardRight,
+    PreviousDay,
+    NextDay,
+    PreviousWeek,
+    NextWeek,
+    Today,
+    Close,
+    ToggleCheck,
+    OpenLink,
+    DeleteEntry,
+    EditComment,
+    Reply,
+    FilterBy(Filter),
+    Field,
+    Query,
+    SetStatus(Status),
+    Tags,
+    Priority,
+    Due,
+    Move,
+    Restore,
+    Save,
+    NextField,
+    /// Sort by the `SortKey::iterator` key at the index, descending when set.
+    SortBy(usize, bool),
+    Select,
+    Submit,
+    Complete,
+    HistoryBack,
+    HistoryForward,
+}
+
+/// Every action with its context, name under `keys:`, help text and
+/// default keys, in the order the Help popup lists them.
+const ACTIONS: &[(Context, Action, &str, &str, &[&str])] = &[
+    (Context::Normal, Action::Quit, "quit", "quit", &["q"]),
+    (Context::Normal, Action::Help, "help", "keybindings", &["?"]),
+    (Context::Normal, Action::NewTask, "new_task", "new task", &["n"]),
+    (Context::Normal, Action::NewSubTask, "new_sub_task", "new sub task", &["s"]),
+    (Context::Normal, Action::EditTask, "edit_task", "edit task", &["e"]),
+    (Context::Normal, Action::ChangeStatus, "change_status", "change status", &["c"]),
+    (Context::Normal, Action::DeleteTask, "delete_task", "delete task", &["D"]),
+    (Context::Normal, Action::AddComment, "add_comment", "add comment", &["a"]),
+    (Context::Normal, Action::EditLastComment, "edit_last_comment", "edit last comment", &["E"]),
+    (Context::Normal, Action::DeleteLastComment, "delete_last_comment", "delete last comment", &["A"]),
+    (Context::Normal, Action::Defer, "defer", "defer until date", &["w"]),
+    (Context::Normal, Action::Assign, "assign", "assign", &["@"]),
+    (Context::Normal, Action::AddLink, "add_link", "add link", &["L"]),
+    (Context::Normal, Action::Templates, "templates", "new from template", &["T"]),
+    (Context::Normal, Action::Duplicate, "duplicate", "duplicate task", &["y"]),
+    (Context::Normal, Action::DuplicateTree, "duplicate_tree", "duplicate with sub tasks and comments", &["Y"]),
+    (Context::Normal, Action::Next, "next", "next task", &["j", "Down"]),
+    (Context::Normal, Action::Previous, "previous", "previous task", &["k", "Up"]),
+    (Context::Normal, Action::ScrollDown, "scroll_down", "scroll details down", &[","]),
+    (Context::Normal, Action::ScrollUp, "scroll_up", "scroll details up", &["."]),
+    (Context::Normal, Action::FocusDetails, "focus_details", "focus details", &["Tab"]),
+    (Context::Normal, Action::Filter, "filter", "filter", &["f"]),
+    (Context::Normal, Action::Sort, "sort", "sort", &["S"]),
+    (Context::Normal, Action::Export, "export", "export", &["X"]),
+    (Context::Normal, Action::Search, "search", "search", &["/"]),
+    (Context::Normal, Action::GoTo, "go_to", "go to task", &["C-p"]),
+    (Context::Normal, Action::Command, "command", "command line", &[":"]),
+    (Context::Normal, Action::Mark, "mark", "mark task", &["m"]),
+    (Context::Normal, Action::Visual, "visual", "mark a range (visual)", &["M"]),
+    (Context::Normal, Action::Bulk, "bulk", "bulk actions on marked tasks", &["b"]),
+    (Context::Normal, Action::Undo, "undo", "undo", &["u"]),
+    (Context::Normal, Action::ArchiveDone, "archive_done", "archive done task", &["z"]),
+    (Context::Normal, Action::Archive, "archive", "browse archive", &["Z"]),
+    (Context::Normal, Action::Views, "views", "pick a view", &["V"]),
+    (Context::Normal, Action::ClearView, "clear_view", "all tasks", &["0"]),
+    (Context::Normal, Action::View(0), "view_1", "view 1", &["1"]),
+    (Context::Normal, Action::View(1), "view_2", "view 2", &["2"]),
+    (Context::Normal, Action::View(2), "view_3", "view 3", &["3"]),
+    (Context::Normal, Action::View(3), "view_4", "view 4", &["4"]),
+    (Context::Normal, Action::View(4), "view_5", "view 5", &["5"]),
+    (Context::Normal, Action::View(5), "view_6", "view 6", &["6"]),
+    (Context::Normal, Action::View(6), "view_7", "view 7", &["7"]),
+    (Context::Normal, Action::View(7), "view_8", "view 8", &["8"]),
+    (Context::Normal, Action::View(8), "view_9", "view 9", &["9"]),
+    (Context::Normal, Action::ToggleGrouping, "toggle_grouping", "group by topic", &["g"]),
+    (Context::Normal, Action::ToggleGroup, "toggle_group", "fold/unfold group", &["Enter", "Space"]),
+    (Context::Normal, Action::NextGroup, "next_group", "next group", &["]"]),
+    (Context::Normal, Action::PreviousGroup, "previous_group", "previous group", &["["]),
+    (Context::Normal, Action::HorizontalLayout, "horizontal_layout", "horizontal view", &["h"]),
+    (Context::Normal, Action::VerticalLayout, "vertical_layout", "vertical view", &["v"]),
+    (Context::Normal, Action::Board, "board", "board view", &["B"]),
+    (Context::Normal, Action::Calendar, "calendar", "calendar view", &["C"]),
+    (Context::Normal, Action::Stats, "stats", "statistics", &["I"]),
+    (Context::Normal, Action::Clear, "clear", "clear marks and search", &["Esc"]),
+    (Context::Search, Action::NextHit, "next_hit", "next search hit", &["n"]),
+    (Context::Search, Action::PreviousHit, "previous_hit", "previous search hit", &["N"]),
+    (Context::Board, Action::ColumnLeft, "column_left", "previous column", &["h", "Left"]),
+    (Context::Board, Action::ColumnRight, "column_right", "next column", &["l", "Right"]),
+    (Context::Board, Action::CardDown, "card_down", "next card", &["j", "Down"]),
+    (Context::Board, Action::CardUp, "card_up", "previous card", &["k", "Up"]),
+    (Context::Board, Action::MoveCardLeft, "move_card_left", "move card left", &["H"]),
+    (Context::Board, Action::MoveCardRight, "move_card_right", "move card right", &["L"]),
+    (Context::Calendar, Action::PreviousDay, "previous_day", "previous day", &["h", "Left"]),
+    (Context::Calendar, Action::NextDay, "next_day", "next day", &["l", "Right"]),
+    (Context::Calendar, Action::PreviousWeek, "previous_week", "previous week", &["H"]),
+    (Context::Calendar, Action::NextWeek, "next_week", "next week", &["L"]),
+    (Context::Calendar, Action::Today, "today", "today", &["t"]),
+    (Context::Details, Action::Close, "close", "back to the list", &["Esc", "Tab"]),
+    (Context::Details, Action::Next, "next", "next entry", &["j", "Down"]),
+    (Context::Details, Action::Previous, "previous", "previous entry", &["k", "Up"]),
+    (Context::Details, Action::ToggleCheck, "toggle_check", "toggle checklist item", &["x", "Space"]),
+    (Context::Details, Action::OpenLink, "open_link", "open link", &["o", "Enter"]),
+    (Context::Details, Action::DeleteEntry, "delete", "delete link or comment", &["D"]),
+    (Context::Details, Action::EditComment, "edit_comment", "edit comment", &["e"]),
+    (Context::Details, Action::Reply, "reply", "reply to comment", &["r"]),
+    (Context::Details, Action::ScrollDown, "scroll_down", "scroll details down", &[","]),
+    (Context::Details, Action::ScrollUp, "scroll_up", "scroll details up", &["."]),
+    (Context::Filter, Action::Close, "close", "close", &["Esc"]),
+    (Context::Filter, Action::FilterBy(Filter::New), "new", "new", &["n"]),
+    (Context::Filter, Action::FilterBy(Filter::Hold), "hold", "hold", &["h"]),
+    (Context::Filter, Action::FilterBy(Filter::InProgress), "in_progress", "in progress", &["i"]),
+    (Context::Filter, Action::FilterBy(Filter::Done), "done", "done", &["d"]),
+    (Context::Filter, Action::FilterBy(Filter::NotDone), "not_done", "not done", &["o"]),
+    (Context::Filter, Action::FilterBy(Filter::Deferred), "deferred", "deferred", &["w"]),
+    (Context::Filter, Action::FilterBy(Filter::Mine), "mine", "mine", &["m"]),
+    (Context::Filter, Action::FilterBy(Filter::All), "all", "all", &["a"]),
+    (Context::Filter, Action::Field, "field", "custom field", &["c"]),
+    (Context::Filter, Action::Query, "query", "query", &["q"]),
+    (Context::Bulk, Action::Close, "close", "close", &["Esc"]),
+    (Context::Bulk, Action::SetStatus(Status::New), "new", "status new", &["n"]),
+    (Context::Bulk, Action::SetStatus(Status::InProgress), "in_progress", "status in progress", &["i"]),
+    (Context::Bulk, Action::SetStatus(Status::Hold), "hold", "status hold", &["h"]),
+    (Context::Bulk, Action::SetStatus(Status::Done), "done", "status done", &["d"]),
+    (Context::Bulk, Action::Tags, "tags", "tags", &["t"]),
+    (Context::Bulk, Action::Priority, "priority", "priority", &["p"]),
+    (Context::Bulk, Action::Due, "due", "due date", &["u"]),
+    (Context::Bulk, Action::Move, "move", "move under a task", &["m"]),
+    (Context::Bulk, Action::Export, "export", "export", &["x"]),
+    (Context::Bulk, Action::DeleteTask, "delete", "delete", &["D"]),
+    (Context::Visual, Action::Close, "close", "end visual mode", &["Esc", "Enter", "M"]),
+    (Context::Visual, Action::Next, "next", "extend down", &["j", "Down"]),
+    (Context::Visual, Action::Previous, "previous", "extend up", &["k", "Up"]),
+    (Context::Visual, Action::Bulk, "bulk", "bulk actions on the range", &["b"]),
+    (Context::Archive, Action::Close, "close", "close", &["Esc"]),
+    (Context::Archive, Action::Next, "next", "next task", &["j", "Down"]),
+    (Context::Archive, Action::Previous, "previous", "previous task", &["k", "Up"]),
+    (Context::Archive, Action::Restore, "restore", "restore task", &["r"]),
+    (Context::Archive, Action::Search, "search", "search", &["/"]),
+    (Context::Popup, Action::Save, "save", "save", &["C-s"]),
+    (Context::Popup, Action::NextField, "next_field", "change popup window", &["Tab"]),
+    (Context::Popup, Action::Close, "close", "close popup", &["Esc"]),
+    (Context::Sort, Action::Close, "close", "close", &["Esc"]),
+    (Context::Sort, Action::SortBy(0, false), "created", "by created", &["c"]),
+    (Context::Sort, Action::SortBy(0, true), "created_desc", "by created, descending", &["C"]),
+    (Context::Sort, Action::SortBy(1, false), "updated", "by last update", &["u"]),
+    (Context::Sort, Action::SortBy(1, true), "updated_desc", "by last update, descending", &["U"]),
+    (Context::Sort, Action::SortBy(2, false), "status", "by status", &["s"]),
+    (Context::Sort, Action::SortBy(2, true), "status_desc", "by status, descending", &["S"]),
+    (Context::Sort, Action::SortBy(3, false), "topic", "by topic", &["t"]),
+    (Context::Sort, Action::SortBy(3, true), "topic_desc", "by topic, descending", &["T"]),
+    (Context::Sort, Action::SortBy(4, false), "name", "by name", &["n"]),
+    (Context::Sort, Action::SortBy(4, true), "name_desc", "by name, descending", &["N"]),
+    (Context::Sort, Action::SortBy(5, false), "priority", "by priority", &["p"]),
+    (Context::Sort, Action::SortBy(5, true), "priority_desc", "by priority, descending", &["P"]),
+    (Context::Sort, Action::SortBy(6, false), "due", "by due date", &["d"]),
+    (Context::Sort, Action::SortBy(6, true), "due_desc", "by due date, descending", &["D"]),
+    (Context::Sort, Action::Field, "field", "by custom field", &["f"]),
+    (Context::Sort, Action::Clear, "clear", "keep current order", &["x"]),
+    (Context::Views, Action::Close, "close", "close", &["Esc"]),
+    (Context::Views, Action::Next, "next", "next view", &["j", "Down"]),
+    (Context::Views, Action::Previous, "previous", "previous view", &["k", "Up"]),
+    (Context::Views, Action::Select, "select", "apply view", &["Enter"]),
+    (Context::Views, Action::View(0), "view_1", "view 1", &["1"]),
+    (Context::Views, Action::View(1), "view_2", "view 2", &["2"]),
+    (Context::Views, Action::View(2), "view_3", "view 3", &["3"]),
+    (Context::Views, Action::View(3), "view_4", "view 4", &["4"]),
+    (Context::Views, Action::View(4), "view_5", "view 5", &["5"]),
+    (Context::Views, Action::View(5), "view_6", "view 6", &["6"]),
+    (Context::Views, Action::View(6), "view_7", "view 7", &["7"]),
+    (Context::Views, Action::View(7), "view_8", "view 8", &["8"]),
+    (Context::Views, Action::View(8), "view_9", "view 9", &["9"]),
+    (Context::Templates, Action::Close, "close", "close", &["Esc"]),
+    (Context::Templates, Action::Next, "next", "next template", &["j", "Down"]),
+    (Context::Templates, Action::Previous, "previous", "previous template", &["k", "Up"]),
+    (Context::Templates, Action::Select, "select", "use template", &["Enter"]),
+    (Context::Stats, Action::Close, "close", "close", &["Esc", "q", "I"]),
+    (Context::Help, Action::Close, "close", "close", &["Esc", "q", "?"]),
+    (Context::Help, Action::ScrollDown, "scroll_down", "scroll down", &["j", "Down"]),
+    (Context::Help, Action::ScrollUp, "scroll_up", "scroll up", &["k", "Up"]),
+    (Context::GoTo, Action::Next, "next", "next match", &["Down", "C-n"]),
+    (Context::GoTo, Action::Previous, "previous", "previous match", &["Up", "C-p"]),
+    (Context::Command, Action::Complete, "complete", "complete", &["Tab"]),
+    (Context::Command, Action::HistoryBack, "history_back", "previous command", &["Up"]),
+    (Context::Command, Action::HistoryForward, "history_forward", "next command", &["Down"]),
+    (Context::Prompt, Action::Submit, "submit", "submit", &["Enter", "C-s"]),
+    (Context::Prompt, Action::Close, "close", "cancel", &["Esc"]),
+];
+
+/// A key with its modifiers. Shift is implied by the character for
+/// printable keys, so `S-a` and `A` are the same chord.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Chord {
+    key: Key,
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+}
+
+impl Chord {
+    fn from_input(input: &Input) -> Chord {
+        match input.key {
+            Key::Char(_) => Chord {
+                key: input.key,
+                ctrl: input.ctrl,
+                alt: input.alt,
+                shift: false,
+            },
+            key => Chord {
+                key,
+                ctrl: input.ctrl,
+                alt: input.alt,
+                shift: input.shift,
+            },
+        }
+    }
+
+    /// Parses `j`, `G`, `C-p`, `A-x`, `S-Tab`, `Enter`, `Space`, `F5`, ...
+    fn parse(input: &str) -> Result<Chord, String> {
+        let mut chord = Chord {
+            key: Key::Null,
+            ctrl: false,
+            alt: false,
+            shift: false,
+        };
+        let mut rest = input;
+        while rest.len() > 2 && rest.as_bytes()[1] == b'-' {
+            match &rest[..2] {
+                "C-" | "c-" => chord.ctrl = true,
+                "A-" | "a-" | "M-" | "m-" => chord.alt = true,
+                "S-" | "s-" => chord.shift = true,
+                _ => break,
+            }
+            rest = &rest[2..];
+        }
+        chord.key = match rest.to_lowercase().as_str() {
+            "enter" => Key::Enter,
+            "esc" => Key::Esc,
+            "tab" => Key::Tab,
+            "backspace" => Key::Backspace,
+            "delete" => Key::Delete,
+            "home" => Key::Home,
+            "end" => Key::End,
+            "pageup" => Key::PageUp,
+            "pagedown" => Key::PageDown,
+            "up" => Key::Up,
+            "down" => Key::Down,
+            "left" => Key::Left,
+            "right" => Key::Right,
+            "space" => Key::Char(' '),
+            f if f.len() > 1 && f.starts_with('f') && f[1..].parse::<u8>().is_ok() => {
+                Key::F(f[1..].parse().unwrap_or(0))
+            }
+            _ => {
+                let mut chars = rest.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Key::Char(c),
+                    _ => return Err(format!("invalid key '{}'", input)),
+                }
+            }
+        };
+        if let Key::Char(c) = chord.key {
+            if chord.shift {
+                chord.key = Key::Char(c.to_ascii_uppercase());
+                chord.shift = false;
+            }
+        }
+        Ok(chord)
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "C-")?;
+        }
+        if self.alt {
+            write!(f, "A-")?;
+        }
+        if self.shift {
+            write!(f, "S-")?;
+        }
+        match self.key {
+            Key::Char(' ') => write!(f, "Space"),
+            Key::Char(c) => write!(f, "{}", c),
+            Key::F(n) => write!(f, "F{}", n),
+            Key::PageUp => write!(f, "PageUp"),
+            Key::PageDown => write!(f, "PageDown"),
+            key => write!(f, "{:?}", key),
+        }
+    }
+}
+
+fn parse_sequence(input: &str) -> Result<Vec<Chord>, String> {
+    let chords = input
+        .split_whitespace()
+        .map(Chord::parse)
+        .collect::<Result<Vec<_>, _>>()?;
+    if chords.is_empty() {
+        return Err("empty key".to_string());
+    }
+    Ok(chords)
+}
+
+fn show_sequence(chords: &[Chord]) -> String {
+    chords.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+pub enum Lookup {
+    Action(Action),
+    /// The keys so far start a longer sequence.
+    Pending,
+    None,
+}
+
+/// The active key bindings: the defaults with the `keys:` overrides of the
+/// config applied.
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: Vec<(Context, Vec<Chord>, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::from_config(&Keys::default()).unwrap_or(Keymap { bindings: Vec::new() })
+    }
+}
+
+impl Keymap {
+    /// Builds the keymap, replacing the default keys of every action named
+    /// in `keys`. Unknown actions, invalid keys and conflicting bindings
+    /// (the same keys, or keys that start another binding of the same
+    /// context) are reported together.
+    pub fn from_config(keys: &Keys) -> Result<Keymap, Vec<String>> {
+        let mut errors = Vec::new();
+        let sections: [(Context, &BTreeMap<String, KeyBinding>); 18] = [
+            (Context::Normal, &keys.normal),
+            (Context::Search, &keys.search),
+            (Context::Board, &keys.board),
+            (Context::Calendar, &keys.calendar),
+            (Context::Details, &keys.details),
+            (Context::Filter, &keys.filter),
+            (Context::Bulk, &keys.bulk),
+            (Context::Visual, &keys.visual),
+            (Context::Archive, &keys.archive),
+            (Context::Popup, &keys.popup),
+            (Context::Sort, &keys.sort),
+            (Context::Views, &keys.views),
+            (Context::Templates, &keys.templates),
+            (Context::Stats, &keys.stats),
+            (Context::Help, &keys.help),
+            (Context::GoTo, &keys.go_to),
+            (Context::Command, &keys.command),
+            (Context::Prompt, &keys.prompt),
+        ];
+        for (context, section) in sections.iter() {
+            for name in section.keys() {
+                if !ACTIONS.iter().any(|(c, _, n, _, _)| c == context && n == name) {
+                    errors.push(format!("keys.{}: unknown action '{}'", context, name));
+                }
+            }
+        }
+
+        let mut bindings: Vec<(Context, Vec<Chord>, Action)> = Vec::new();
+        for (context, action, name, _, defaults) in ACTIONS {
+            let section = sections.iter().find(|(c, _)| c == context).map(|(_, s)| s);
+            let keys: Vec<String> = match section.and_then(|s| s.get(*name)) {
+                Some(KeyBinding::One(key)) => vec![key.clone()],
+                Some(KeyBinding::Many(keys)) => keys.clone(),
+                None => defaults.iter().map(|k| k.to_string()).collect(),
+            };
+            for key in keys {
+                match parse_sequence(&key) {
+                    Ok(chords) => bindings.push((*context, chords, *action)),
+                    Err(e) => errors.push(format!("keys.{}.{}: {}", context, name, e)),
+                }
+            }
+        }
+
+        for (n, (context, chords, action)) in bindings.iter().enumerate() {
+            for (other_context, other, other_action) in bindings.iter().skip(n + 1) {
+                let shorter = chords.len().min(other.len());
+                if context == other_context && chords[..shorter] == other[..shorter] {
+                    errors.push(format!(
+                        "keys.{}: '{}' ({}) conflicts with '{}' ({})",
+                        context,
+                        show_sequence(chords),
+                        action_name(*context, *action),
+                        show_sequence(other),
+                        action_name(*context, *other_action),
+                    ));
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(Keymap { bindings })
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Resolves the keys typed so far. `contexts` are tried in order, so
+    /// the first one that binds (or starts binding) the keys wins.
+    pub fn lookup(&self, contexts: &[Context], pending: &[Chord]) -> Lookup {
+        for context in contexts {
+            let mut started = false;
+            for (_, chords, action) in self.bindings.iter().filter(|(c, _, _)| c == context) {
+                if chords.as_slice() == pending {
+                    return Lookup::Action(*action);
+                }
+                started = started || chords.starts_with(pending);
+            }
+            if started {
+                return Lookup::Pending;
+            }
+        }
+        Lookup::None
+    }
+
+    /// Keys and help text of every action, per context.
+    pub fn help(&self) -> Vec<(Context, String, &'static str)> {
+        ACTIONS
+            .iter()
+            .map(|(context, action, _, description, _)| (*context, self.keys(*context, *action), *description))
+            .filter(|(_, keys, _)| !keys.is_empty())
+            .collect()
+    }
+
+    /// The keys bound to `action`, e.g. `j/Down`, empty when unbound.
+    pub fn keys(&self, context: Context, action: Action) -> String {
+        let keys: Vec<String> = self
+            .bindings
+            .iter()
+            .filter(|(c, _, a)| *c == context && *a == action)
+            .map(|(_, chords, _)| show_sequence(chords))
+            .collect();
+        keys.join("/")
+    }
+}
+
+fn action_name(context: Context, action: Action) -> &'static str {
+    ACTIONS
+        .iter()
+        .find(|(c, a, _, _, _)| *c == context && *a == action)
+        .map_or("", |(_, _, name, _, _)| *name)
+}
+
+/// Adds a key press to the keys typed so far.
+pub fn push_input(pending: &mut Vec<Chord>, input: &Input) {
+    pending.push(Chord::from_input(input));
+}
+
+pub fn show_pending(pending: &[Chord]) -> String {
+    show_sequence(pending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::app::SortKey;
+
+    fn chords(keys: &str) -> Vec<Chord> {
+        parse_sequence(keys).expect("valid keys")
+    }
+
+    fn keys(normal: &[(&str, &[&str])]) -> Keys {
+        let mut keys = Keys::default();
+        for (name, bound) in normal {
+            let bound = bound.iter().map(|k| k.to_string()).collect();
+            keys.normal.insert(name.to_string(), KeyBinding::Many(bound));
+        }
+        keys
+    }
+
+    fn action(keymap: &Keymap, contexts: &[Context], keys: &str) -> Option<Action> {
+        match keymap.lookup(contexts, &chords(keys)) {
+            Lookup::Action(action) => Some(action),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn parses_keys_with_modifiers() {
+        let ctrl_p = Chord::parse("C-p").unwrap();
+        assert!(ctrl_p.ctrl && ctrl_p.key == Key::Char('p'));
+        assert_eq!(Chord::parse("S-a"), Chord::parse("A"));
+        assert_eq!(Chord::parse("space").unwrap().key, Key::Char(' '));
+        assert_eq!(Chord::parse("F5").unwrap().key, Key::F(5));
+        assert_eq!(Chord::parse("F").unwrap().key, Key::Char('F'));
+        assert_eq!(Chord::parse("S-Tab").unwrap().to_string(), "S-Tab");
+        assert_eq!(show_sequence(&chords("g  C-t")), "g C-t");
+        assert!(Chord::parse("ab").is_err());
+        assert!(parse_sequence("  ").is_err());
+    }
+
+    #[test]
+    fn defaults_have_no_conflicts() {
+        let keymap = Keymap::from_config(&Keys::default()).unwrap();
+        assert_eq!(action(&keymap, &[Context::Normal], "q"), Some(Action::Quit));
+        assert_eq!(keymap.keys(Context::Normal, Action::Next), "j/Down");
+        assert_eq!(action(&keymap, &[Context::Popup], "C-s"), Some(Action::Save));
+    }
+
+    #[test]
+    fn first_context_wins() {
+        let keymap = Keymap::default();
+        let board = [Context::Board, Context::Normal];
+        assert_eq!(action(&keymap, &board, "h"), Some(Action::ColumnLeft));
+        assert_eq!(action(&keymap, &board, "q"), Some(Action::Quit));
+        assert_eq!(action(&keymap, &[Context::Normal], "h"), Some(Action::HorizontalLayout));
+        assert_eq!(action(&keymap, &[Context::Details], "q"), None);
+    }
+
+    #[test]
+    fn inputs_fall_back_to_the_prompt_context() {
+        let keymap = Keymap::default();
+        let command = [Context::Command, Context::Prompt];
+        assert_eq!(action(&keymap, &command, "Tab"), Some(Action::Complete));
+        assert_eq!(action(&keymap, &command, "Enter"), Some(Action::Submit));
+        assert_eq!(action(&keymap, &[Context::GoTo, Context::Prompt], "C-p"), Some(Action::Previous));
+        assert_eq!(action(&keymap, &[Context::Prompt], "x"), None);
+    }
+
+    #[test]
+    fn sort_actions_follow_the_sort_keys() {
+        for (i, key) in SortKey::iterator().enumerate() {
+            assert_eq!(action_name(Context::Sort, Action::SortBy(i, false)), key.to_string());
+            assert_eq!(action_name(Context::Sort, Action::SortBy(i, true)), format!("{}_desc", key));
+        }
+    }
+
+    #[test]
+    fn config_replaces_and_unbinds_keys() {
+        let keymap = Keymap::from_config(&keys(&[("quit", &["C-c"]), ("stats", &[])])).unwrap();
+        assert_eq!(action(&keymap, &[Context::Normal], "C-c"), Some(Action::Quit));
+        assert_eq!(action(&keymap, &[Context::Normal], "q"), None);
+        assert_eq!(action(&keymap, &[Context::Normal], "I"), None);
+        assert!(!keymap.help().iter().any(|(_, _, d)| *d == "statistics"));
+    }
+
+    #[test]
+    fn sequences_wait_for_the_next_key() {
+        let keymap =
+            Keymap::from_config(&keys(&[("go_to", &["g t"]), ("toggle_grouping", &["g g"])])).unwrap();
+        assert!(matches!(keymap.lookup(&[Context::Normal], &chords("g")), Lookup::Pending));
+        assert_eq!(action(&keymap, &[Context::Normal], "g t"), Some(Action::GoTo));
+        assert_eq!(action(&keymap, &[Context::Normal], "g g"), Some(Action::ToggleGrouping));
+        assert!(matches!(keymap.lookup(&[Context::Normal], &chords("g x")), Lookup::None));
+    }
+
+    #[test]
+    fn reports_every_error() {
+        let errors = Keymap::from_config(&keys(&[
+            ("go_to", &["g t"]),
+            ("jump", &["J"]),
+            ("help", &["C-"]),
+        ]))
+        .unwrap_err();
+        assert_eq!(
+            errors,
+            [
+                "keys.normal: unknown action 'jump'",
+                "keys.normal.help: invalid key 'C-'",
+                "keys.normal: 'g t' (go_to) conflicts with 'g' (toggle_grouping)",
+            ]
+        );
+    }
+
+    #[test]
+    fn same_keys_conflict_within_a_context_only() {
+        let mut config = keys(&[("undo", &["q"])]);
+        config.board.insert("card_down".to_string(), KeyBinding::One("j".to_string()));
+        let errors = Keymap::from_config(&config).unwrap_err();
+        assert_eq!(errors, ["keys.normal: 'q' (quit) conflicts with 'q' (undo)"]);
+    }
+}
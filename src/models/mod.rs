@@ -5,3 +5,11 @@ pub mod task;
 pub mod time;
 pub mod os;
 pub mod config;
+pub mod template;
+pub mod export;
+pub mod archive;
+pub mod query;
+pub mod fuzzy;
+pub mod stats;
+pub mod keymap;
+pub mod command;
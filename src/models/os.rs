@@ -1,12 +1,18 @@
 use std::env;
 use std::fs;
+use std::io;
 use std::path::Path;
+use std::process::{Command, Stdio};
 
 pub trait FileSystem {
     fn home_dir() -> String {
         return env::var("HOME").expect("$HOME dir not exist");
     }
 
+    fn config_path() -> String {
+        format!("{}/.config/todo", Self::home_dir())
+    }
+
     fn get_path() -> String {
         let path = format!("{}/.todo", Self::home_dir());
         if !Path::new(&path).is_dir() {
@@ -21,4 +27,14 @@ pub trait FileSystem {
             Err(_) => false,
         }
     }
+
+    fn open_external(target: &str) -> io::Result<()> {
+        Command::new("xdg-open")
+            .arg(target)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map(|_| ())
+    }
 }
@@ -0,0 +1,462 @@
+use std::cmp::Ordering;
+
+use super::{
+    config::CustomField,
+    task::{Priority, Status, Task},
+    time::parse_date,
+};
+
+const KEYS: &str = "status, topic, tag, priority, assignee, text, due";
+
+/// A parsed filter query such as
+/// `status:new,hold topic:backend tag:urgent due<7d text:"login"`.
+///
+/// Terms are combined with `AND` (also implied between terms), `OR` and
+/// `NOT` (or a leading `-`); parentheses group. Comma separated values of a
+/// single term match any of them.
+#[derive(Debug, Clone)]
+pub enum Query {
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    Term(Term),
+}
+
+#[derive(Debug, Clone)]
+pub enum Term {
+    Status(Vec<Status>),
+    Topic(Vec<String>),
+    Tag(Vec<String>),
+    Priority(Ordering, Vec<Priority>),
+    Assignee(Vec<String>),
+    Text(String),
+    Due(Due),
+    Field(CustomField, Ordering, Vec<String>),
+}
+
+#[derive(Debug, Clone)]
+pub enum Due {
+    Before(i64),
+    After(i64),
+    On(i64),
+    Any,
+    Missing,
+    Overdue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Word(String),
+}
+
+impl Query {
+    pub fn parse(input: &str, fields: &[CustomField]) -> Result<Query, String> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            return Err("empty query".to_string());
+        }
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            fields,
+        };
+        let query = parser.or()?;
+        match parser.peek() {
+            None => Ok(query),
+            Some(Token::Close) => Err("unexpected ')'".to_string()),
+            Some(t) => Err(format!("unexpected {}", describe(t))),
+        }
+    }
+
+    pub fn matches(&self, task: &Task, now: i64) -> bool {
+        match self {
+            Query::And(a, b) => a.matches(task, now) && b.matches(task, now),
+            Query::Or(a, b) => a.matches(task, now) || b.matches(task, now),
+            Query::Not(q) => !q.matches(task, now),
+            Query::Term(term) => term.matches(task, now),
+        }
+    }
+}
+
+impl Term {
+    fn matches(&self, task: &Task, now: i64) -> bool {
+        match self {
+            Term::Status(statuses) => statuses.contains(&task.status),
+            Term::Topic(topics) => topics
+                .iter()
+                .any(|t| task.topic.to_lowercase().contains(t)),
+            Term::Tag(tags) => tags
+                .iter()
+                .any(|t| task.tags.iter().any(|tag| tag.to_lowercase() == *t)),
+            Term::Priority(ordering, priorities) => match task.priority {
+                Some(p) => priorities.iter().any(|v| p.cmp(v) == *ordering),
+                None => false,
+            },
+            Term::Assignee(names) => match &task.assignee {
+                Some(a) => names.iter().any(|n| a.to_lowercase() == *n),
+                None => names.iter().any(|n| n == "none"),
+            },
+            Term::Text(text) => task.contains_text(text),
+            Term::Due(due) => match (due, task.due_timestamp) {
+                (Due::Missing, d) => d.is_none(),
+                (Due::Any, d) => d.is_some(),
+                (Due::Overdue, _) => task.is_overdue(now),
+                (Due::Before(t), Some(d)) => d < *t,
+                (Due::After(t), Some(d)) => d > *t,
+                (Due::On(t), Some(d)) => d >= *t && d < t + 24 * 60 * 60,
+                (_, None) => false,
+            },
+            Term::Field(field, ordering, values) => match task.fields.get(&field.name) {
+                Some(v) => values.iter().any(|value| match ordering {
+                    Ordering::Equal => v.eq_ignore_ascii_case(value),
+                    _ => field.compare(v, value) == *ordering,
+                }),
+                None => false,
+            },
+        }
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Open => "'('".to_string(),
+        Token::Close => "')'".to_string(),
+        Token::And => "AND".to_string(),
+        Token::Or => "OR".to_string(),
+        Token::Not => "NOT".to_string(),
+        Token::Word(w) => format!("'{}'", w),
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            ' ' | '\t' => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '-' if tokens.last() != Some(&Token::Not) => {
+                chars.next();
+                tokens.push(Token::Not);
+            }
+            _ => {
+                let mut word = String::new();
+                let mut quoted = false;
+                while let Some(&c) = chars.peek() {
+                    match c {
+                        '"' => {
+                            chars.next();
+                            quoted = true;
+                            loop {
+                                match chars.next() {
+                                    Some('"') => break,
+                                    Some(c) => word.push(c),
+                                    None => return Err(format!("unterminated quote in '{}'", word)),
+                                }
+                            }
+                        }
+                        ' ' | '\t' | '(' | ')' => break,
+                        _ => {
+                            chars.next();
+                            word.push(c);
+                        }
+                    }
+                }
+                tokens.push(match word.as_str() {
+                    _ if quoted => Token::Word(word),
+                    "AND" | "and" | "&&" => Token::And,
+                    "OR" | "or" | "||" => Token::Or,
+                    "NOT" | "not" | "!" => Token::Not,
+                    _ => Token::Word(word),
+                });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    fields: &'a [CustomField],
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Query, String> {
+        let mut query = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            query = Query::Or(Box::new(query), Box::new(self.and()?));
+        }
+        Ok(query)
+    }
+
+    fn and(&mut self) -> Result<Query, String> {
+        let mut query = self.not()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.next();
+                }
+                Some(Token::Open | Token::Not | Token::Word(_)) => (),
+                _ => return Ok(query),
+            }
+            query = Query::And(Box::new(query), Box::new(self.not()?));
+        }
+    }
+
+    fn not(&mut self) -> Result<Query, String> {
+        if self.peek() == Some(&Token::Not) {
+            self.next();
+            return Ok(Query::Not(Box::new(self.not()?)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Query, String> {
+        match self.next() {
+            Some(Token::Open) => {
+                let query = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(query),
+                    _ => Err("missing ')'".to_string()),
+                }
+            }
+            Some(Token::Word(word)) => Ok(Query::Term(self.term(&word)?)),
+            Some(t) => Err(format!("expected a term, found {}", describe(&t))),
+            None => Err("query ends unexpectedly".to_string()),
+        }
+    }
+
+    fn term(&self, word: &str) -> Result<Term, String> {
+        let split = word
+            .char_indices()
+            .find(|(_, c)| *c == ':' || *c == '<' || *c == '>');
+        let (key, ordering, value) = match split {
+            Some((at, c)) => {
+                let ordering = match c {
+                    '<' => Ordering::Less,
+                    '>' => Ordering::Greater,
+                    _ => Ordering::Equal,
+                };
+                (word[..at].to_lowercase(), ordering, word[at + 1..].trim())
+            }
+            None => return Ok(Term::Text(word.to_string())),
+        };
+        if value.is_empty() {
+            return Err(format!("'{}' needs a value", key));
+        }
+        let values: Vec<String> = value
+            .split(',')
+            .map(|v| v.trim().to_lowercase())
+            .filter(|v| !v.is_empty())
+            .collect();
+        let comparable = matches!(key.as_str(), "priority" | "due")
+            || self.field(&key).is_some_and(|f| f.kind != "text");
+        if ordering != Ordering::Equal && !comparable {
+            return Err(format!("'{}' does not support < or >, use {}:value", key, key));
+        }
+        match key.as_str() {
+            "status" => values
+                .iter()
+                .map(|v| v.parse::<Status>())
+                .collect::<Result<Vec<_>, _>>()
+                .map(Term::Status),
+            "topic" => Ok(Term::Topic(values)),
+            "tag" => Ok(Term::Tag(
+                values.iter().map(|v| v.trim_start_matches('#').to_string()).collect(),
+            )),
+            "assignee" => Ok(Term::Assignee(
+                values.iter().map(|v| v.trim_start_matches('@').to_string()).collect(),
+            )),
+            "text" => Ok(Term::Text(value.to_string())),
+            "priority" => values
+                .iter()
+                .map(|v| v.parse::<Priority>())
+                .collect::<Result<Vec<_>, _>>()
+                .map(|p| Term::Priority(ordering, p)),
+            "due" => parse_due(ordering, value).map(Term::Due),
+            // values keep their case and are checked against the field type,
+            // enums compare by the declared spelling
+            _ => match self.field(&key) {
+                Some(field) => value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+                    .map(|v| field.validate(v))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(|values| Term::Field(field.clone(), ordering, values)),
+                None => Err(format!("unknown key '{}' (use {} or a custom field)", key, KEYS)),
+            },
+        }
+    }
+
+    /// The custom field named `key`, ignoring case.
+    fn field(&self, key: &str) -> Option<&CustomField> {
+        self.fields.iter().find(|f| f.name.eq_ignore_ascii_case(key))
+    }
+}
+
+/// `due` accepts `none`, `any`, `overdue`, a date or a relative offset like
+/// `7d` (from now).
+fn parse_due(ordering: Ordering, value: &str) -> Result<Due, String> {
+    let value = value.to_lowercase();
+    match (ordering, value.as_str()) {
+        (Ordering::Equal, "none") => return Ok(Due::Missing),
+        (Ordering::Equal, "any") => return Ok(Due::Any),
+        (Ordering::Equal, "overdue") => return Ok(Due::Overdue),
+        _ => (),
+    }
+    let relative = match value.strip_suffix(['h', 'd', 'w']) {
+        Some(amount) => !amount.is_empty() && amount.chars().all(|c| c.is_ascii_digit()),
+        None => false,
+    };
+    let date = if relative {
+        parse_date(&format!("+{}", value))
+    } else {
+        parse_date(&value)
+    };
+    match (date, ordering) {
+        (Some(t), Ordering::Less) => Ok(Due::Before(t)),
+        (Some(t), Ordering::Greater) => Ok(Due::After(t)),
+        (Some(t), Ordering::Equal) => Ok(Due::On(t)),
+        (None, _) => Err(format!(
+            "invalid due date '{}' (7d, 2w, today, dd-mm-yyyy, none, any, overdue)",
+            value
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::time::{current_timestamp, DAY};
+
+    fn task(topic: &str, name: &str, status: Status, tags: &[&str]) -> Task {
+        let mut task = Task::create(Some(topic.to_string()), name.to_string(), None, None, None);
+        task.status = status;
+        task.tags = tags.iter().map(|t| t.to_string()).collect();
+        task
+    }
+
+    fn tasks() -> Vec<Task> {
+        let mut list = vec![
+            task("backend", "login page", Status::New, &["urgent"]),
+            task("backend", "db migration", Status::Hold, &[]),
+            task("frontend", "dark mode", Status::Done, &["urgent", "ui"]),
+        ];
+        list[0].priority = Some(Priority::High);
+        list[1].priority = Some(Priority::Low);
+        list[1].set_due(Some(current_timestamp() + 3 * DAY));
+        list
+    }
+
+    fn names(query: &str) -> Vec<String> {
+        let query = Query::parse(query, &[]).expect("valid query");
+        let now = current_timestamp();
+        tasks()
+            .into_iter()
+            .filter(|t| query.matches(t, now))
+            .map(|t| t.name)
+            .collect()
+    }
+
+    #[test]
+    fn terms_are_and_combined_by_default() {
+        assert_eq!(names("topic:backend tag:urgent"), ["login page"]);
+        assert_eq!(names("topic:backend AND status:hold"), ["db migration"]);
+    }
+
+    #[test]
+    fn comma_values_match_any_of_them() {
+        assert_eq!(names("status:new,done"), ["login page", "dark mode"]);
+    }
+
+    #[test]
+    fn or_binds_looser_than_and() {
+        assert_eq!(
+            names("status:done OR topic:backend tag:urgent"),
+            ["login page", "dark mode"]
+        );
+        assert_eq!(names("(status:done OR topic:backend) tag:urgent").len(), 2);
+        assert_eq!(names("-tag:urgent"), ["db migration"]);
+        assert_eq!(names("NOT topic:backend"), ["dark mode"]);
+    }
+
+    #[test]
+    fn priority_and_due_compare() {
+        assert_eq!(names("priority>low"), ["login page"]);
+        assert_eq!(names("due<7d"), ["db migration"]);
+        assert_eq!(names("due>7d"), Vec::<String>::new());
+        assert_eq!(names("due:none").len(), 2);
+        assert_eq!(names("text:\"dark mode\""), ["dark mode"]);
+    }
+
+    #[test]
+    fn invalid_queries_are_errors() {
+        let error = |q: &str| Query::parse(q, &[]).unwrap_err();
+        assert_eq!(error(""), "empty query");
+        assert_eq!(error("(status:new"), "missing ')'");
+        assert_eq!(error("status:new)"), "unexpected ')'");
+        assert!(error("color:red").starts_with("unknown key 'color'"));
+        assert!(error("status:").contains("needs a value"));
+        assert!(error("topic<x").contains("does not support < or >"));
+        assert!(error("status:bogus").starts_with("unknown status"));
+    }
+
+    #[test]
+    fn custom_fields_ignore_the_case_of_names_and_values() {
+        let fields = [CustomField {
+            name: "Severity".to_string(),
+            kind: "enum".to_string(),
+            values: vec!["Low".to_string(), "High".to_string(), "Critical".to_string()],
+        }];
+        let mut list = tasks();
+        for (task, severity) in list.iter_mut().zip(["Low", "High", "Critical"]) {
+            task.fields.insert("Severity".to_string(), severity.to_string());
+        }
+        let names = |query: &str| -> Vec<String> {
+            let query = Query::parse(query, &fields).expect("valid query");
+            list.iter()
+                .filter(|t| query.matches(t, 0))
+                .map(|t| t.name.clone())
+                .collect()
+        };
+        assert_eq!(names("severity>low"), ["db migration", "dark mode"]);
+        assert_eq!(names("Severity<CRITICAL"), ["login page", "db migration"]);
+        assert_eq!(names("SEVERITY:high"), ["db migration"]);
+        let error = Query::parse("severity>urgent", &fields).unwrap_err();
+        assert_eq!(error, "Severity must be one of Low, High, Critical");
+    }
+
+    #[test]
+    fn multibyte_due_values_are_errors() {
+        for value in ["due:é", "due<3é", "due<aé", "due>+é"] {
+            assert!(Query::parse(value, &[]).is_err(), "{}", value);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use super::{
+    task::{Status, Task},
+    time::{day_start, week_start, DAY},
+};
+
+/// When a task was completed: the last status change of a done task.
+fn completed_at(task: &Task) -> Option<i64> {
+    match task.status {
+        Status::Done => task.status_change_timestamp,
+        _ => None,
+    }
+}
+
+/// Created and completed counts for the last `count` periods of `period`
+/// seconds (a day or a week), oldest first, as `(start, created, completed)`.
+pub fn created_completed(tasks: &[&Task], now: i64, period: i64, count: usize) -> Vec<(i64, u64, u64)> {
+    let current = if period == DAY { day_start(now) } else { week_start(now) };
+    (0..count as i64)
+        .rev()
+        .map(|n| {
+            let start = current - n * period;
+            let within = |t: i64| t >= start && t < start + period;
+            let created = tasks.iter().filter(|t| within(t.creation_timestamp)).count();
+            let completed = tasks
+                .iter()
+                .filter(|t| completed_at(t).is_some_and(within))
+                .count();
+            (start, created as u64, completed as u64)
+        })
+        .collect()
+}
+
+/// Open (not done) tasks at the end of each of the last `days` days, as
+/// `(days ago, open)` points for a chart.
+pub fn burndown(tasks: &[&Task], now: i64, days: i64) -> Vec<(f64, f64)> {
+    let today = day_start(now);
+    (0..days)
+        .rev()
+        .map(|n| {
+            let end = today - n * DAY + DAY;
+            let open = tasks
+                .iter()
+                .filter(|t| t.creation_timestamp < end && completed_at(t).is_none_or(|c| c >= end))
+                .count();
+            (-(n as f64), open as f64)
+        })
+        .collect()
+}
+
+/// Average time from creation to completion of the done tasks of each topic,
+/// in days, slowest first.
+pub fn lead_time_by_topic(tasks: &[&Task]) -> Vec<(String, f64)> {
+    let mut topics: Vec<(String, i64, i64)> = Vec::new();
+    for task in tasks {
+        let lead = match completed_at(task) {
+            Some(c) => c - task.creation_timestamp,
+            None => continue,
+        };
+        match topics.iter_mut().find(|(t, _, _)| *t == task.topic) {
+            Some((_, total, count)) => {
+                *total += lead;
+                *count += 1;
+            }
+            None => topics.push((task.topic.clone(), lead, 1)),
+        }
+    }
+    let mut averages: Vec<(String, f64)> = topics
+        .into_iter()
+        .map(|(topic, total, count)| (topic, total as f64 / count as f64 / DAY as f64))
+        .collect();
+    averages.sort_by(|a, b| b.1.total_cmp(&a.1));
+    averages
+}
+
+pub fn status_counts(tasks: &[&Task]) -> Vec<(Status, u64)> {
+    [Status::New, Status::InProgress, Status::Hold, Status::Done]
+        .into_iter()
+        .map(|s| (s, tasks.iter().filter(|t| t.status == s).count() as u64))
+        .collect()
+}
@@ -1,6 +1,7 @@
-use super::time::{current_timestamp, duration, time_delta, to_human_date};
+use super::time::{current_timestamp, duration, time_delta, to_human_date, DAY};
 use core::fmt;
 use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, str::FromStr};
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Status {
@@ -29,10 +30,108 @@ impl fmt::Display for Status {
     }
 }
 
+impl FromStr for Status {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "new" => Ok(Status::New),
+            "progress" | "in progress" | "inprogress" | "in_progress" => Ok(Status::InProgress),
+            "hold" => Ok(Status::Hold),
+            "done" => Ok(Status::Done),
+            _ => Err(format!("unknown status '{}' (new, progress, hold, done)", s)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub enum Priority {
+    #[serde(alias = "low")]
+    Low,
+    #[serde(alias = "medium")]
+    Medium,
+    #[serde(alias = "high")]
+    High,
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Priority::Low => write!(f, "LOW"),
+            Priority::Medium => write!(f, "MEDIUM"),
+            Priority::High => write!(f, "HIGH"),
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "low" | "l" => Ok(Priority::Low),
+            "medium" | "m" => Ok(Priority::Medium),
+            "high" | "h" => Ok(Priority::High),
+            _ => Err(format!("unknown priority '{}' (low, medium, high)", s)),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Comment {
+    #[serde(default)]
+    pub id: i64,
     pub date: String,
     pub text: String,
+    #[serde(default)]
+    pub edited: Option<String>,
+    #[serde(default)]
+    pub reply_to: Option<i64>,
+    #[serde(default)]
+    pub author: Option<String>,
+}
+
+impl Comment {
+    /// Names mentioned as `@name` in the comment text.
+    pub fn mentions(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for word in self.text.split_whitespace() {
+            if let Some(name) = word.strip_prefix('@') {
+                let name: String = name
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || *c == '-' || *c == '_' || *c == '.')
+                    .collect();
+                let name = name.trim_end_matches('.').to_string();
+                if !name.is_empty() && !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+        names
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LinkKind {
+    File,
+    Url,
+    Task,
+}
+
+impl fmt::Display for LinkKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkKind::File => write!(f, "file"),
+            LinkKind::Url => write!(f, "url"),
+            LinkKind::Task => write!(f, "task"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Link {
+    pub kind: LinkKind,
+    pub target: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -52,6 +151,32 @@ pub struct Task {
     pub parent_id: Option<i64>,
     pub is_sub_task: bool,
     pub display: bool,
+    #[serde(default)]
+    pub defer_timestamp: Option<i64>,
+    #[serde(default)]
+    pub defer_date: Option<String>,
+    #[serde(default)]
+    pub links: Vec<Link>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub priority: Option<Priority>,
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
+    #[serde(default)]
+    pub assignee: Option<String>,
+    #[serde(default)]
+    pub due_timestamp: Option<i64>,
+    #[serde(default)]
+    pub due_date: Option<String>,
+    #[serde(default)]
+    pub archived_timestamp: Option<i64>,
+    #[serde(default)]
+    pub archived_date: Option<String>,
+    /// Id of the next comment; ids of deleted comments are never reused so
+    /// replies keep pointing at the comment they answered.
+    #[serde(default)]
+    pub next_comment_id: i64,
 }
 
 impl Task {
@@ -79,12 +204,220 @@ impl Task {
             parent_id: None,
             is_sub_task: is_sub_task.unwrap_or(false),
             display: true,
+            defer_timestamp: None,
+            defer_date: None,
+            links: Vec::new(),
+            tags: Vec::new(),
+            priority: None,
+            fields: BTreeMap::new(),
+            assignee: None,
+            due_timestamp: None,
+            due_date: None,
+            archived_timestamp: None,
+            archived_date: None,
+            next_comment_id: 1,
         };
     }
 
-    pub fn create_comment(&mut self, text: String) {
+    pub fn defer(&mut self, timestamp: Option<i64>) {
+        self.defer_timestamp = timestamp;
+        self.defer_date = timestamp.map(to_human_date);
+    }
+
+    pub fn set_due(&mut self, timestamp: Option<i64>) {
+        self.due_timestamp = timestamp;
+        self.due_date = timestamp.map(to_human_date);
+    }
+
+    pub fn is_overdue(&self, now: i64) -> bool {
+        match self.due_timestamp {
+            Some(t) => t < now && self.status != Status::Done,
+            None => false,
+        }
+    }
+
+    /// Whether the task is due on, or was completed on, the day starting at
+    /// `day`.
+    pub fn on_day(&self, day: i64) -> bool {
+        let within = |t: i64| t >= day && t < day + DAY;
+        self.due_timestamp.is_some_and(within)
+            || (self.status == Status::Done && self.status_change_timestamp.is_some_and(within))
+    }
+
+    /// Applies `+tag` / `-tag` edits; plain words replace all tags.
+    pub fn retag(&mut self, input: &str) {
+        let words: Vec<&str> = input.split([' ', ',']).filter(|w| !w.is_empty()).collect();
+        if words.iter().all(|w| w.starts_with('+') || w.starts_with('-')) {
+            for word in words {
+                let tag = word[1..].trim_start_matches('#').to_string();
+                if word.starts_with('+') {
+                    if !self.tags.contains(&tag) {
+                        self.tags.push(tag);
+                    }
+                } else {
+                    self.tags.retain(|t| *t != tag);
+                }
+            }
+        } else {
+            self.tags = words
+                .iter()
+                .map(|w| w.trim_start_matches('#').to_string())
+                .collect();
+        }
+    }
+
+    pub fn set_archived(&mut self, timestamp: Option<i64>) {
+        self.archived_timestamp = timestamp;
+        self.archived_date = timestamp.map(to_human_date);
+    }
+
+    /// Case-insensitive match against name, description, topic, tags and
+    /// comments. An empty query matches every task.
+    pub fn contains_text(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        query.is_empty()
+            || self.name.to_lowercase().contains(&query)
+            || self.description.to_lowercase().contains(&query)
+            || self.topic.to_lowercase().contains(&query)
+            || self.tags.iter().any(|t| t.to_lowercase().contains(&query))
+            || self
+                .comments
+                .iter()
+                .any(|c| c.text.to_lowercase().contains(&query))
+    }
+
+    pub fn is_deferred(&self, now: i64) -> bool {
+        match self.defer_timestamp {
+            Some(t) => t > now,
+            None => false,
+        }
+    }
+
+    /// Markdown checklist items (`- [ ]` / `- [x]`) of the description as
+    /// `(line index, checked)` pairs.
+    pub fn checklist(&self) -> Vec<(usize, bool)> {
+        self.description
+            .lines()
+            .enumerate()
+            .filter_map(|(idx, line)| checklist_marker(line).map(|checked| (idx, checked)))
+            .collect()
+    }
+
+    /// Flips the `[ ]`/`[x]` marker of the item on `line_index`, leaving the
+    /// rest of the description (item text, line endings) untouched.
+    pub fn toggle_checklist_item(&mut self, line_index: usize) {
+        let lines: Vec<String> = self
+            .description
+            .split('\n')
+            .enumerate()
+            .map(|(idx, line)| match marker_position(line) {
+                Some((at, checked)) if idx == line_index => {
+                    let marker = if checked { "[ ]" } else { "[x]" };
+                    format!("{}{}{}", &line[..at], marker, &line[at + 3..])
+                }
+                _ => line.to_string(),
+            })
+            .collect();
+        self.description = lines.join("\n");
+    }
+
+    pub fn create_comment(&mut self, text: String, reply_to: Option<i64>, author: Option<String>) {
         let date = to_human_date(current_timestamp());
-        self.comments.push(Comment { date, text })
+        let id = self.comment_id_floor().max(self.next_comment_id);
+        self.next_comment_id = id + 1;
+        self.comments.push(Comment {
+            id,
+            date,
+            text,
+            edited: None,
+            reply_to,
+            author,
+        })
+    }
+
+    /// Copy of the task that starts over as `Status::New`. Ids and tree links
+    /// are left to the caller.
+    pub fn duplicate(&self, keep_comments: bool) -> Task {
+        let c_time = current_timestamp();
+        let mut task = self.clone();
+        task.status = Status::New;
+        task.creation_timestamp = c_time;
+        task.creation_date = to_human_date(c_time);
+        task.status_change_timestamp = None;
+        task.status_change_date = None;
+        task.duration = None;
+        task.display = true;
+        if !keep_comments {
+            task.comments = Vec::new();
+        }
+        task
+    }
+
+    /// Whether the task is assigned to `name` or mentions `name` in a comment.
+    pub fn involves(&self, name: &str) -> bool {
+        self.assignee.as_deref() == Some(name)
+            || self.comments.iter().any(|c| c.mentions().iter().any(|m| m == name))
+    }
+
+    pub fn edit_comment(&mut self, id: i64, text: String) {
+        if let Some(comment) = self.comments.iter_mut().find(|c| c.id == id) {
+            comment.text = text;
+            comment.edited = Some(to_human_date(current_timestamp()));
+        }
+    }
+
+    pub fn delete_comment(&mut self, id: i64) {
+        self.comments.retain(|c| c.id != id);
+    }
+
+    /// Gives comments stored before ids existed a stable, unique id. Lists
+    /// saved without `next_comment_id` continue after the highest id.
+    pub fn normalize_comment_ids(&mut self) {
+        let mut next_id = self.comment_id_floor().max(self.next_comment_id);
+        let mut seen: Vec<i64> = Vec::new();
+        for comment in self.comments.iter_mut() {
+            if comment.id == 0 || seen.contains(&comment.id) {
+                comment.id = next_id;
+                next_id += 1;
+            }
+            seen.push(comment.id);
+        }
+        self.next_comment_id = next_id;
+    }
+
+    /// One past the highest comment id in use.
+    fn comment_id_floor(&self) -> i64 {
+        self.comments.iter().map(|c| c.id).max().unwrap_or(0) + 1
+    }
+
+    /// Moves the task straight to `status`, keeping the timestamps consistent
+    /// with what `change_status` would have produced.
+    pub fn set_status(&mut self, status: Status) {
+        if self.status == status {
+            return;
+        }
+        let c_time = current_timestamp();
+        self.status = status;
+        match status {
+            Status::New => {
+                self.status_change_timestamp = None;
+                self.status_change_date = None;
+                self.duration = None;
+            }
+            Status::InProgress => {
+                self.status_change_timestamp = Some(c_time);
+                self.status_change_date = None;
+                self.duration = None;
+            }
+            Status::Hold | Status::Done => {
+                self.status_change_timestamp = Some(c_time);
+                self.status_change_date = Some(to_human_date(c_time));
+                self.duration = Some(duration(time_delta(
+                    self.creation_timestamp,
+                    self.status_change_timestamp,
+                )));
+            }
+        }
     }
 
     pub fn change_status(&mut self, is_sub_task_done:bool) {
@@ -130,3 +463,68 @@ impl Task {
     }
 }
 
+
+fn checklist_marker(line: &str) -> Option<bool> {
+    marker_position(line).map(|(_, checked)| checked)
+}
+
+/// Byte offset and state of the `[ ]`/`[x]` marker right after the `- ` or
+/// `* ` prefix of a checklist line.
+fn marker_position(line: &str) -> Option<(usize, bool)> {
+    let trimmed = line.trim_start();
+    let item = trimmed
+        .strip_prefix("- ")
+        .or_else(|| trimmed.strip_prefix("* "))?;
+    let at = line.len() - item.len();
+    if item.starts_with("[ ]") {
+        Some((at, false))
+    } else if item.starts_with("[x]") || item.starts_with("[X]") {
+        Some((at, true))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task_with_comments(n: usize) -> Task {
+        let mut task = Task::create(None, "task".to_string(), None, None, None);
+        for i in 0..n {
+            task.create_comment(format!("comment {}", i), None, None);
+        }
+        task
+    }
+
+    #[test]
+    fn deleted_comment_ids_are_not_reused() {
+        let mut task = task_with_comments(2);
+        task.create_comment("reply".to_string(), Some(2), None);
+        task.delete_comment(2);
+        task.delete_comment(3);
+        task.create_comment("new".to_string(), None, None);
+        let ids: Vec<i64> = task.comments.iter().map(|c| c.id).collect();
+        assert_eq!(ids, [1, 4]);
+    }
+
+    #[test]
+    fn next_id_survives_a_save() {
+        let mut task = task_with_comments(3);
+        task.delete_comment(3);
+        let mut task: Task = serde_json::from_str(&serde_json::to_string(&task).unwrap()).unwrap();
+        task.normalize_comment_ids();
+        task.create_comment("new".to_string(), None, None);
+        assert_eq!(task.comments.last().map(|c| c.id), Some(4));
+    }
+
+    #[test]
+    fn lists_without_next_id_continue_after_the_highest_id() {
+        let mut task = task_with_comments(2);
+        let mut value = serde_json::to_value(&task).unwrap();
+        value.as_object_mut().unwrap().remove("next_comment_id");
+        task = serde_json::from_value(value).unwrap();
+        task.normalize_comment_ids();
+        assert_eq!(task.next_comment_id, 3);
+    }
+}
@@ -0,0 +1,138 @@
+use serde::Deserialize;
+use std::{fs, io::ErrorKind};
+
+use super::{
+    os::FileSystem,
+    task::{Priority, Task},
+};
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SubTaskTemplate {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub priority: Option<Priority>,
+}
+
+/// A reusable task tree stored in `~/.config/todo/templates.yaml`. Every
+/// string may contain `{{placeholder}}` markers that are prompted for when
+/// the template is used.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Template {
+    pub name: String,
+    #[serde(default)]
+    pub topic: Option<String>,
+    pub task: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub priority: Option<Priority>,
+    #[serde(default)]
+    pub sub_tasks: Vec<SubTaskTemplate>,
+}
+
+impl Template {
+    /// Reads `templates.yaml`; a missing file means no templates, an
+    /// unreadable or invalid one is an error.
+    pub fn load_all() -> Result<Vec<Template>, String> {
+        let path = format!("{}/templates.yaml", Template::config_path());
+        match fs::read_to_string(&path) {
+            Ok(s) => serde_yaml::from_str::<Vec<Template>>(&s)
+                .map_err(|e| format!("{}: {}", path, e)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(format!("{}: {}", path, e)),
+        }
+    }
+
+    /// Placeholder names in order of first appearance.
+    pub fn placeholders(&self) -> Vec<String> {
+        let mut texts = vec![
+            self.topic.clone().unwrap_or_default(),
+            self.task.clone(),
+            self.description.clone(),
+        ];
+        texts.extend(self.tags.iter().cloned());
+        for sub_task in self.sub_tasks.iter() {
+            texts.push(sub_task.name.clone());
+            texts.push(sub_task.description.clone());
+            texts.extend(sub_task.tags.iter().cloned());
+        }
+        let mut names: Vec<String> = Vec::new();
+        for text in texts {
+            for (_, _, name) in placeholder_spans(&text) {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+        names
+    }
+
+    /// Builds the parent task followed by its sub tasks. Ids and tree links
+    /// are assigned by the caller.
+    pub fn build(&self, values: &[(String, String)]) -> (Task, Vec<Task>) {
+        let fill = |text: &str| -> String {
+            let mut filled = String::new();
+            let mut last = 0;
+            for (start, end, name) in placeholder_spans(text) {
+                if let Some((_, value)) = values.iter().find(|(n, _)| *n == name) {
+                    filled.push_str(&text[last..start]);
+                    filled.push_str(value);
+                    last = end;
+                }
+            }
+            filled.push_str(&text[last..]);
+            filled
+        };
+        let mut parent = Task::create(
+            self.topic.as_deref().map(fill),
+            fill(&self.task),
+            Some(fill(&self.description)),
+            None,
+            None,
+        );
+        parent.tags = self.tags.iter().map(|t| fill(t)).collect();
+        parent.priority = self.priority;
+        let children = self
+            .sub_tasks
+            .iter()
+            .map(|s| {
+                let mut child = Task::create(
+                    Some(parent.topic.clone()),
+                    fill(&s.name),
+                    Some(fill(&s.description)),
+                    None,
+                    Some(true),
+                );
+                child.tags = s.tags.iter().map(|t| fill(t)).collect();
+                child.priority = s.priority;
+                child
+            })
+            .collect();
+        (parent, children)
+    }
+}
+
+impl FileSystem for Template {}
+
+/// Byte ranges and trimmed names of the `{{ name }}` placeholders in `text`.
+fn placeholder_spans(text: &str) -> Vec<(usize, usize, String)> {
+    let mut spans = Vec::new();
+    let mut from = 0;
+    while let Some(start) = text[from..].find("{{").map(|i| from + i) {
+        match text[start + 2..].find("}}") {
+            Some(len) => {
+                let end = start + 2 + len + 2;
+                spans.push((start, end, text[start + 2..start + 2 + len].trim().to_string()));
+                from = end;
+            }
+            None => break,
+        }
+    }
+    spans
+}
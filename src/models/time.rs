@@ -22,3 +22,73 @@ pub fn duration(seconds: i64) -> String {
         d.num_minutes() % 60
     );
 }
+
+/// Parses user input such as `25-12-2024`, `25-12-2024 09:30`, `today`,
+/// `tomorrow` or a relative offset like `+3d`, `+2w`, `+4h`.
+pub fn parse_date(input: &str) -> Option<i64> {
+    let input = input.trim();
+    let today = Utc::now().date_naive().and_hms_opt(0, 0, 0)?.and_utc();
+    match input {
+        "today" => return Some(today.timestamp()),
+        "tomorrow" => return Some((today + Duration::days(1)).timestamp()),
+        _ => (),
+    }
+    if let Some(offset) = input.strip_prefix('+') {
+        let (at, unit) = offset.char_indices().last()?;
+        let amount: i64 = offset[..at].parse().ok()?;
+        let delta = match unit {
+            'h' => Duration::hours(amount),
+            'd' => Duration::days(amount),
+            'w' => Duration::weeks(amount),
+            _ => return None,
+        };
+        return Some(current_timestamp() + delta.num_seconds());
+    }
+    if let Ok(dt) = NaiveDateTime::parse_from_str(input, "%d-%m-%Y %H:%M") {
+        return Some(dt.and_utc().timestamp());
+    }
+    if let Ok(d) = NaiveDate::parse_from_str(input, "%d-%m-%Y") {
+        return Some(d.and_hms_opt(0, 0, 0)?.and_utc().timestamp());
+    }
+    None
+}
+
+pub const DAY: i64 = 24 * 60 * 60;
+
+/// Start of the day `timestamp` falls on.
+pub fn day_start(timestamp: i64) -> i64 {
+    timestamp - timestamp.rem_euclid(DAY)
+}
+
+/// Start of the week (Monday) `timestamp` falls in.
+pub fn week_start(timestamp: i64) -> i64 {
+    let day = day_start(timestamp);
+    let weekday = DateTime::from_timestamp(day, 0)
+        .map_or(0, |d| d.weekday().num_days_from_monday() as i64);
+    day - weekday * DAY
+}
+
+pub fn to_day(timestamp: i64) -> String {
+    let dt = DateTime::from_timestamp(timestamp, 0).expect("invalid timestamp");
+    dt.format("%d-%m-%Y").to_string()
+}
+
+/// Title ("October 2026") and the day starts of the month `timestamp` falls
+/// in, padded with `None` so that the first week starts on a Monday.
+pub fn month_days(timestamp: i64) -> (String, Vec<Option<i64>>) {
+    let dt = DateTime::from_timestamp(timestamp, 0).expect("invalid timestamp");
+    let first = NaiveDate::from_ymd_opt(dt.year(), dt.month(), 1).expect("invalid date");
+    let title = first.format("%B %Y").to_string();
+    let mut days: Vec<Option<i64>> = (0..first.weekday().num_days_from_monday())
+        .map(|_| None)
+        .collect();
+    let mut day = first;
+    while day.month() == first.month() {
+        days.push(day.and_hms_opt(0, 0, 0).map(|d| d.and_utc().timestamp()));
+        day = match day.succ_opt() {
+            Some(d) => d,
+            None => break,
+        };
+    }
+    (title, days)
+}
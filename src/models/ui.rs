@@ -1,8 +1,12 @@
 use super::{
-    app::{App, Filter, InputArea, InputMode, PageLayout},
+    app::{App, DetailEntry, Filter, InputArea, InputMode, PageLayout, Row, SortKey, BOARD_COLUMNS},
     config::AppConfig,
     formatter::Formatter,
-    task::{Status, Task},
+    fuzzy::fuzzy_match,
+    keymap::{show_pending, Action, Context, Keymap},
+    stats,
+    task::{LinkKind, Priority, Status, Task},
+    time::{current_timestamp, day_start, month_days, to_day, DAY},
 };
 use ratatui::{prelude::*, widgets::*};
 use std::{iter::once, str::FromStr};
@@ -11,7 +15,7 @@ use tui_textarea::TextArea;
 pub fn ui(
     f: &mut Frame,
     app: &mut App,
-    text_area: &mut [TextArea; 4],
+    text_area: &mut [TextArea; 6],
     conf: &AppConfig,
     formatter: &Formatter,
 ) {
@@ -25,43 +29,108 @@ pub fn ui(
     )
     .split(f.size());
 
+    let now = current_timestamp();
     let selected_style = get_selected_style();
     let border_type = get_border_type(conf);
     let border_style = Style::new().fg(Color::from_str(conf.colors.border_color.as_str()).unwrap());
     let text_style =
         Style::new().fg(Color::from_str(conf.colors.task_text_color.as_str()).unwrap());
 
-    let info_text = vec![text::Line::from(
-        " <down/up>|<j/k>:move, <q>:quit, ?:keybidings",
-    )];
-    let info_paragraph =
-        Paragraph::new(info_text).fg(Color::from_str(&conf.colors.footer_color.as_str()).unwrap());
+    let match_style = Style::new()
+        .fg(Color::Black)
+        .bg(Color::from_str(conf.colors.footer_color.as_str()).unwrap());
+    let info_text = match app.input_mode {
+        InputMode::Search => {
+            let query = text_area[4].lines().concat();
+            f.set_cursor(main_layout[2].x + 1 + text_area[4].cursor().1 as u16, main_layout[2].y);
+            Some(vec![text::Line::from(format!("/{}", query))])
+        }
+        InputMode::Command => {
+            let line = text_area[4].lines().concat();
+            f.set_cursor(main_layout[2].x + 1 + text_area[4].cursor().1 as u16, main_layout[2].y);
+            let mut spans = vec![Span::raw(format!(":{}", line))];
+            if let Some(e) = &app.prompt_error {
+                spans.push(Span::styled(
+                    format!("  {}", e),
+                    Style::new().fg(Color::from_str(conf.colors.icon_hold_color.as_str()).unwrap()),
+                ));
+            } else if !app.completions.is_empty() {
+                spans.push(Span::raw(format!("  [{}]", app.completions.join(" "))));
+            }
+            Some(vec![text::Line::from(spans)])
+        }
+        _ if !app.pending_keys.is_empty() => Some(vec![text::Line::from(format!(
+            " {} ...",
+            show_pending(&app.pending_keys)
+        ))]),
+        _ => None,
+    };
+    match info_text {
+        Some(info_text) => {
+            let info_paragraph = Paragraph::new(info_text)
+                .fg(Color::from_str(conf.colors.footer_color.as_str()).unwrap());
+            f.render_widget(info_paragraph, main_layout[2]);
+        }
+        None => render_status_bar(f, app, main_layout[2], conf, now),
+    }
 
-    f.render_widget(info_paragraph, main_layout[2]);
+    let header_style = Style::new().fg(Color::from_str(conf.colors.header_color.as_str()).unwrap());
+    let mut header = vec![Span::styled(
+        format!(" {} ", app.view.and_then(|i| app.views.get(i)).map_or("All tasks", |v| v.name.as_str())),
+        header_style.bold().reversed(),
+    )];
+    for (i, view) in app.views.iter().enumerate().take(9) {
+        let style = if app.view == Some(i) { header_style.bold() } else { header_style };
+        header.push(Span::styled(format!("  {}:{}", i + 1, view.name), style));
+    }
+    if let Some(e) = &app.view_error {
+        header.push(Span::styled(
+            format!("  {}", e),
+            Style::new().fg(Color::from_str(conf.colors.icon_hold_color.as_str()).unwrap()),
+        ));
+    }
+    f.render_widget(Paragraph::new(Line::from(header)), main_layout[0]);
 
+    let calendar_split = Layout::new(
+        Direction::Horizontal,
+        [Constraint::Length(32), Constraint::Min(0)],
+    )
+    .split(main_layout[1]);
     let inner_layout = {
         match app.layout {
             PageLayout::Vertical => Layout::new(
                 Direction::Horizontal,
-                [Constraint::Percentage(50), Constraint::Percentage(50)],
+                [Constraint::Percentage(app.split), Constraint::Percentage(100 - app.split)],
             )
             .split(main_layout[1]),
             PageLayout::Horizontal => Layout::new(
                 Direction::Vertical,
-                [Constraint::Percentage(50), Constraint::Percentage(50)],
+                [Constraint::Percentage(app.split), Constraint::Percentage(100 - app.split)],
+            )
+            .split(main_layout[1]),
+            PageLayout::Board => Layout::new(
+                Direction::Vertical,
+                [Constraint::Percentage(60), Constraint::Percentage(40)],
             )
             .split(main_layout[1]),
+            PageLayout::Calendar => Layout::new(
+                Direction::Vertical,
+                [Constraint::Percentage(50), Constraint::Percentage(50)],
+            )
+            .split(calendar_split[1]),
         }
     };
 
-    let items: Vec<ListItem> = app
-        .items
-        .clone()
+    app.areas.list = inner_layout[0];
+    app.areas.details = inner_layout[1];
+    app.areas.fields.clear();
+
+    // sub task connectors are worked out over the displayed tasks, in store
+    // order, and looked up per row
+    let displayed: Vec<usize> = (0..app.items.len()).filter(|i| app.items[*i].display).collect();
+    let codes: Vec<usize> = displayed
         .iter()
-        .filter(|f| f.display)
-        // .map(|x| (x.child_list.len(), x.is_sub_task))
-        .map(|x| (display_child_list(&x.child_list, &app.items), x.is_sub_task))
-        .into_iter()
+        .map(|i| (display_child_list(&app.items[*i].child_list, &app.items), app.items[*i].is_sub_task))
         .scan(0, |state, (x, y)| {
             if !y {
                 *state = x + 1;
@@ -75,21 +144,86 @@ pub fn ui(
                 return Some(1);
             }
         })
-        .zip(app.items.iter().filter(|f| f.display))
-        .map(|(x, t)| {
-            let mut topic = topic_formatter(x, t, conf);
-            let lines = {
-                let styled = Span::styled(t.name.as_str(), text_style);
-                if t.status == Status::Done {
-                    styled
-                        .crossed_out()
-                        .fg(Color::from_str(conf.colors.task_text_color_done.as_str()).unwrap())
-                } else {
-                    styled
-                }
-            };
-            topic.extend(vec![lines]);
-            ListItem::new(Line::from(topic))
+        .collect();
+
+    let view: &App = app;
+    let task_item = move |x: usize, i: usize| {
+        let t = &view.items[i];
+        let mut topic = topic_formatter(x, t, conf);
+        if view.marked.contains(&t.id) {
+            topic.insert(0, Span::styled(
+                conf.icons.marked.clone(),
+                Style::new().fg(Color::from_str(conf.colors.footer_color.as_str()).unwrap()).bold(),
+            ));
+        }
+        if let Some(p) = t.priority {
+            topic.push(Span::styled(
+                format!("{} ", priority_marker(p)),
+                Style::new().fg(Color::from_str(conf.colors.icon_hold_color.as_str()).unwrap()).bold(),
+            ));
+        }
+        let lines = {
+            let styled = Span::styled(t.name.as_str(), text_style);
+            if t.status == Status::Done {
+                styled
+                    .crossed_out()
+                    .fg(Color::from_str(conf.colors.task_text_color_done.as_str()).unwrap())
+            } else {
+                styled
+            }
+        };
+        topic.extend(vec![lines]);
+        if let Some(assignee) = &t.assignee {
+            topic.push(Span::styled(
+                format!(" @{}", assignee),
+                Style::new().fg(Color::from_str(conf.colors.header_color.as_str()).unwrap()),
+            ));
+        }
+        for tag in t.tags.iter() {
+            topic.push(Span::styled(
+                format!(" #{}", tag),
+                Style::new().fg(Color::from_str(conf.colors.task_date_color.as_str()).unwrap()),
+            ));
+        }
+        let checklist = t.checklist();
+        if !checklist.is_empty() {
+            let checked = checklist.iter().filter(|(_, c)| *c).count();
+            topic.push(Span::styled(
+                format!(" ☑ {}/{}", checked, checklist.len()),
+                Style::new().fg(Color::from_str(conf.colors.task_duration_color.as_str()).unwrap()),
+            ));
+        }
+        if !t.child_list.is_empty() {
+            let (done, total) = view.progress(t);
+            topic.push(Span::styled(
+                format!(" {} {}/{}", progress_bar(done, total), done, total),
+                Style::new().fg(Color::from_str(conf.colors.task_duration_color.as_str()).unwrap()),
+            ));
+        }
+        if let Some(date) = &t.due_date {
+            let color = if t.is_overdue(now) { conf.colors.icon_hold_color.as_str() } else { conf.colors.task_date_color.as_str() };
+            topic.push(Span::styled(
+                format!(" due {}", date),
+                Style::new().fg(Color::from_str(color).unwrap()),
+            ));
+        }
+        if let Some(date) = t.defer_date.as_ref().filter(|_| view.filter == Filter::Deferred) {
+            topic.push(Span::styled(
+                format!(" until {}", date),
+                Style::new().fg(Color::from_str(conf.colors.task_date_color.as_str()).unwrap()),
+            ));
+        }
+        ListItem::new(highlight_line(Line::from(topic), &view.search_query, match_style))
+    };
+    let items: Vec<ListItem> = app
+        .rows()
+        .into_iter()
+        .map(|row| match row {
+            Row::Header(topic) => group_header(app, &topic, conf),
+            Row::Task(i) => {
+                let x = displayed.iter().position(|d| *d == i).map_or(0, |p| codes[p]);
+                task_item(x, i)
+            }
         })
         .collect();
 
@@ -99,12 +233,29 @@ pub fn ui(
                 .borders(Borders::ALL)
                 .border_type(border_type)
                 .border_style(border_style)
-                .title("Tasks"),
+                .title(match (app.input_mode, app.marked.len(), app.calendar_date) {
+                    (InputMode::Visual, n, _) => format!("Tasks [visual, {} marked]", n),
+                    (_, 0, Some(day)) => format!("Tasks [{}]", to_day(day)),
+                    (_, 0, None) => "Tasks".to_string(),
+                    (_, n, _) => format!("Tasks [{} marked]", n),
+                }),
         )
         .highlight_style(selected_style)
         .highlight_symbol(conf.icons.cursor.as_str());
 
-    f.render_stateful_widget(list, inner_layout[0], &mut app.state);
+    if app.layout == PageLayout::Calendar {
+        render_calendar(f, app, calendar_split[0], conf, border_type, border_style);
+    }
+    if app.layout == PageLayout::Board {
+        render_board(f, app, inner_layout[0], conf, border_type, border_style);
+    } else {
+        // `app.state` holds a store index, the widget needs the visible row
+        let mut list_state = ListState::default()
+            .with_selected(app.selected_row())
+            .with_offset(app.list_offset);
+        f.render_stateful_widget(list, inner_layout[0], &mut list_state);
+        app.list_offset = list_state.offset();
+    }
 
     let details = match app.state.selected() {
         Some(i) => { let paragraph = {
@@ -114,8 +265,13 @@ pub fn ui(
             let child_list = &app.sub_items;
             let task_status_last_change = task.status_change_date.clone().unwrap_or("-//-".to_string());
             let task_duration = task.duration.clone().unwrap_or("-//-".to_string());
-            let formatter_name = formatter.format(&task.name);
-            let formatter_desc = formatter.format(&task.description);
+            let formatter_name = highlight_text(formatter.format(&task.name), &app.search_query, match_style);
+            let mut formatter_desc = highlight_text(formatter.format(&task.description), &app.search_query, match_style);
+            if let (InputMode::Details, Some(DetailEntry::Check(line))) = (app.input_mode, app.detail_entry()) {
+                if let Some(l) = formatter_desc.lines.get_mut(line) {
+                    l.patch_style(selected_style.add_modifier(Modifier::REVERSED));
+                }
+            }
             let mut text_block = Text::raw("");
             let mut info_block = vec![
                 Text::from(
@@ -132,9 +288,15 @@ pub fn ui(
                     )
                 ),
                 Text::raw(""),
+                Text::styled(format!("id:       {}", &task.id), Style::default().fg(Color::from_str(conf.colors.task_date_color.as_str()).unwrap())),
                 Text::styled(format!("created:  {}", &task.creation_date), Style::default().fg(Color::from_str(conf.colors.task_date_color.as_str()).unwrap())),
                 Text::styled(format!("updated:  {}", &task.status_change_date.clone().unwrap_or("-//-".to_string())), Style::default().fg(Color::from_str(conf.colors.task_date_color.as_str()).unwrap())),
                 Text::styled(format!("duration: {}", &task.duration.clone().unwrap_or("-//-".to_string())), Style::default().fg(Color::from_str(conf.colors.task_date_color.as_str()).unwrap())),
+                Text::styled(format!("due:      {}{}", &task.due_date.clone().unwrap_or("-//-".to_string()), if task.is_overdue(now) { " (overdue)" } else { "" }), Style::default().fg(Color::from_str(if task.is_overdue(now) { conf.colors.icon_hold_color.as_str() } else { conf.colors.task_date_color.as_str() }).unwrap())),
+                Text::styled(format!("deferred: {}", &task.defer_date.clone().unwrap_or("-//-".to_string())), Style::default().fg(Color::from_str(conf.colors.task_date_color.as_str()).unwrap())),
+                Text::styled(format!("assignee: {}", task.assignee.as_ref().map(|a| format!("@{}", a)).unwrap_or("-//-".to_string())), Style::default().fg(Color::from_str(conf.colors.task_date_color.as_str()).unwrap())),
+                Text::styled(format!("priority: {}", task.priority.map(|p| p.to_string()).unwrap_or("-//-".to_string())), Style::default().fg(Color::from_str(conf.colors.task_date_color.as_str()).unwrap())),
+                Text::styled(format!("tags:     {}", if task.tags.is_empty() { "-//-".to_string() } else { task.tags.iter().map(|t| format!("#{}", t)).collect::<Vec<_>>().join(" ") }), Style::default().fg(Color::from_str(conf.colors.task_date_color.as_str()).unwrap())),
                 Text::raw(""),
                 Text::styled("Name:", Style::default().fg(Color::from_str(conf.colors.header_color.as_str()).unwrap()).bold()),
                 formatter_name,
@@ -150,12 +312,40 @@ pub fn ui(
             ];
 
             let mut comments:Vec<_> = task.comments.iter()
-                .map(|x| (x.date.clone(), x.text.clone()))
-                .flat_map(|(a, b)| {
-                    let c_date = Text::styled(format!("date: {}", a), Style::default().fg(Color::from_str(conf.colors.task_date_color.as_str()).unwrap()));
-                    let c_text = formatter.format(&b);
+                .enumerate()
+                .flat_map(|(idx, x)| {
+                    let mut header = Line::from(vec![
+                        Span::styled(format!("#{} ", x.id), Style::default().fg(Color::from_str(conf.colors.header_color.as_str()).unwrap())),
+                        Span::styled(format!("date: {}", x.date), Style::default().fg(Color::from_str(conf.colors.task_date_color.as_str()).unwrap())),
+                    ]);
+                    if let Some(author) = &x.author {
+                        header.spans.push(Span::styled(format!(" by @{}", author), Style::default().fg(Color::from_str(conf.colors.header_color.as_str()).unwrap())));
+                    }
+                    if let Some(edited) = &x.edited {
+                        header.spans.push(Span::styled(format!(" (edited: {})", edited), Style::default().fg(Color::from_str(conf.colors.task_date_color.as_str()).unwrap())));
+                    }
+                    if let (InputMode::Details, Some(DetailEntry::Comment(selected))) = (app.input_mode, app.detail_entry()) {
+                        if selected == idx {
+                            header.patch_style(selected_style.add_modifier(Modifier::REVERSED));
+                        }
+                    }
+                    let reply = x.reply_to.map(|r| Text::styled(format!("↳ reply to #{}", r), Style::default().fg(Color::from_str(conf.colors.task_date_color.as_str()).unwrap())));
+                    let c_text = highlight_text(formatter.format(&x.text), &app.search_query, match_style);
+                    let mentions = x.mentions();
+                    let mentions = if mentions.is_empty() {
+                        None
+                    } else {
+                        Some(Text::from(Line::from(
+                            once(Span::styled("→ ", Style::default().fg(Color::from_str(conf.colors.task_date_color.as_str()).unwrap())))
+                                .chain(mentions.into_iter().map(|m| {
+                                    let style = Style::default().fg(Color::from_str(conf.colors.header_color.as_str()).unwrap());
+                                    Span::styled(format!("@{} ", m), if m == app.user_name { style.bold().underlined() } else { style })
+                                }))
+                                .collect::<Vec<_>>(),
+                        )))
+                    };
                     let wrapper = Text::styled(wrapper.clone(), Style::default().fg(Color::from_str(conf.colors.border_color.as_str()).unwrap()));
-                    once(c_date).chain(once(c_text)).chain(once(wrapper))
+                    once(Text::from(header)).chain(reply).chain(once(c_text)).chain(mentions).chain(once(wrapper))
                 })
                 .collect();
 
@@ -181,6 +371,49 @@ pub fn ui(
                 ]
             ))).chain(vec![Text::raw("")]).collect();
 
+            let mut fields_block = vec![
+                Text::styled("Fields:", Style::default().fg(Color::from_str(conf.colors.header_color.as_str()).unwrap()).bold()),
+            ];
+
+            let mut fields:Vec<_> = task.fields.iter().map(|(name, value)| Text::from(Line::from(vec![
+                Span::styled(format!(" {}: ", name), Style::default().fg(Color::from_str(conf.colors.task_date_color.as_str()).unwrap())),
+                Span::styled(value.clone(), text_style),
+            ]))).chain(vec![Text::raw("")]).collect();
+
+            if fields.len() > 1 {
+                info_block.append(&mut fields_block);
+                info_block.append(&mut fields);
+            }
+
+            let mut links_block = vec![
+                Text::styled("Links:", Style::default().fg(Color::from_str(conf.colors.header_color.as_str()).unwrap()).bold()),
+            ];
+
+            let mut links:Vec<_> = task.links.iter().enumerate().map(|(idx, l)| {
+                let label = match l.kind {
+                    LinkKind::Task => app.linked_task(l).map(|t| t.name.clone()).unwrap_or(l.target.clone()),
+                    _ => l.target.clone(),
+                };
+                let mut line = Line::from(vec![
+                    Span::styled(format!(" {:<5}", l.kind.to_string()), Style::default().fg(Color::from_str(conf.colors.task_date_color.as_str()).unwrap())),
+                    Span::styled(label, text_style),
+                ]);
+                if app.link_missing(l) {
+                    line.spans.push(Span::styled(" (missing)", Style::default().fg(Color::from_str(conf.colors.icon_hold_color.as_str()).unwrap()).bold()));
+                }
+                if let (InputMode::Details, Some(DetailEntry::Link(selected))) = (app.input_mode, app.detail_entry()) {
+                    if selected == idx {
+                        line.patch_style(selected_style.add_modifier(Modifier::REVERSED));
+                    }
+                }
+                Text::from(line)
+            }).chain(vec![Text::raw("")]).collect();
+
+            if links.len() > 1 {
+                info_block.append(&mut links_block);
+                info_block.append(&mut links);
+            }
+
             if sub_tasks.len() > 1 {
                 info_block.append(&mut sub_tasks_block);
                 info_block.append(&mut sub_tasks);
@@ -208,14 +441,24 @@ pub fn ui(
     .block(Block::default()
                 .borders(Borders::ALL)
                 .border_type(border_type)
-                .border_style(border_style)
-                .title("Details"))
+                .border_style(match app.input_mode {
+                    InputMode::Details => border_style.add_modifier(Modifier::BOLD),
+                    _ => border_style,
+                })
+                .title(match app.input_mode {
+                    InputMode::Details => details_title(app),
+                    _ => "Details".to_string(),
+                }))
                 .scroll((app.scroll as u16, 0));
 
     f.render_widget(details, inner_layout[1]);
 
     match app.input_mode {
-        InputMode::Normal => (),
+        InputMode::Normal
+        | InputMode::Details
+        | InputMode::Visual
+        | InputMode::Search
+        | InputMode::Command => (),
         InputMode::Comment | InputMode::CommentEdit => {
             let layout = centered_rect(50, 30, f.size());
             activate(
@@ -229,6 +472,51 @@ pub fn ui(
             f.render_widget(Clear, layout[2][0]);
             f.render_widget(widget_comment, layout[2][0]);
         }
+        InputMode::Defer
+        | InputMode::Link
+        | InputMode::TemplateFill
+        | InputMode::FieldFilter
+        | InputMode::Query
+        | InputMode::FieldSort
+        | InputMode::Export
+        | InputMode::Assign
+        | InputMode::BulkTags
+        | InputMode::BulkPriority
+        | InputMode::BulkDue
+        | InputMode::BulkMove => {
+            let layout = centered_rect(50, 30, f.size());
+            let (name, hint) = match app.input_mode {
+                InputMode::Link => ("Add link".to_string(), "url, file path or #task".to_string()),
+                InputMode::FieldFilter => ("Filter by field".to_string(), "field=value, empty to clear".to_string()),
+                InputMode::Query => ("Query".to_string(), "status:new,hold tag:urgent due<7d text:\"login\", AND/OR/NOT".to_string()),
+                InputMode::FieldSort => ("Sort by field".to_string(), "field, -field for descending".to_string()),
+                InputMode::Assign => ("Assignee".to_string(), "name, empty to unassign".to_string()),
+                InputMode::Export => ("Export to".to_string(), "path .md or .json, empty for ~/.todo/export.md".to_string()),
+                InputMode::BulkTags => ("Tags".to_string(), "+tag -tag to edit, words to replace, empty to clear".to_string()),
+                InputMode::BulkPriority => ("Priority".to_string(), "low, medium, high, empty to clear".to_string()),
+                InputMode::BulkDue => ("Due".to_string(), "dd-mm-yyyy [hh:mm], +3d, tomorrow, empty to clear".to_string()),
+                InputMode::BulkMove => ("Move under".to_string(), "task id or name, empty for top level".to_string()),
+                InputMode::TemplateFill => (
+                    format!("{{{{{}}}}}", app.template_values.get(app.template_field).map(|v| v.0.as_str()).unwrap_or("")),
+                    format!("{}/{}", app.template_field + 1, app.template_values.len()),
+                ),
+                _ => ("Defer until".to_string(), "dd-mm-yyyy [hh:mm], +3d, tomorrow, empty to clear".to_string()),
+            };
+            let title = match &app.prompt_error {
+                Some(e) => format!("{} ({})", name, e),
+                None => format!("{} ({})", name, hint),
+            };
+            activate(
+                &mut text_area[4],
+                title,
+                border_type,
+                border_style,
+                text_style,
+            );
+            let widget_prompt = text_area[4].widget();
+            f.render_widget(Clear, layout[0][0]);
+            f.render_widget(widget_prompt, layout[0][0]);
+        }
         InputMode::SubTask | InputMode::SubTaskModify => {
             let layout = centered_rect(50, 30, f.size());
             match app.input_area {
@@ -264,6 +552,22 @@ pub fn ui(
                         text_style,
                     );
                 }
+                InputArea::Fields => {
+                    inactivate(
+                        &mut text_area[1],
+                        "Sub Task".to_string(),
+                        border_type,
+                        border_style,
+                        text_style,
+                    );
+                    inactivate(
+                        &mut text_area[2],
+                        "Description".to_string(),
+                        border_type,
+                        border_style,
+                        text_style,
+                    );
+                }
                 _ => (),
             }
 
@@ -274,38 +578,390 @@ pub fn ui(
             f.render_widget(widget_task, layout[1][0]);
             f.render_widget(Clear, layout[2][0]);
             f.render_widget(widget_desc, layout[2][0]);
+            let fields_area = render_fields(f, app, &mut text_area[5], layout[2][0], border_type, border_style, text_style);
+            app.areas.fields.push((layout[1][0], InputArea::Task));
+            app.areas.fields.push((layout[2][0], InputArea::Description));
+            if let Some(area) = fields_area {
+                app.areas.fields.push((area, InputArea::Fields));
+            }
         }
         InputMode::Help => {
             let info_block_popup = Block::default()
-                .title("Keybidings")
+                .title(format!(
+                    "Keybidings [{}, {}: scroll]",
+                    app.keymap.keys(Context::Help, Action::ScrollDown),
+                    app.keymap.keys(Context::Help, Action::ScrollUp)
+                ))
                 .borders(Borders::ALL)
                 .border_style(border_style)
                 .border_type(border_type);
 
             let info_layout_popup = info_rect(40, 50, f.size());
-            let text = vec![
-                text::Line::from("<esc>: \t\t close popup"),
-                text::Line::from("<n>: \t\t new task"),
-                text::Line::from("<s>: \t\t new sub task"),
-                text::Line::from("<c>: \t\t change status"),
-                text::Line::from("<e>: \t\t edit task"),
-                text::Line::from("<E>: \t\t edit last comment"),
-                text::Line::from("<a>: \t\t add comment"),
-                text::Line::from("<D>: \t\t delete task"),
-                text::Line::from("<A>: \t\t delete last comment"),
-                text::Line::from("<h>: \t\t horizontal view"),
-                text::Line::from("<v>: \t\t vertical view"),
-                text::Line::from("<Tab>: \t\t change popup window"),
-                text::Line::from("<C-s>: \t\t save"),
-                text::Line::from("<,>: \t\t scroll down"),
-                text::Line::from("<.>: \t\t scroll up"),
-            ];
+            let mut text = Vec::new();
+            let mut context = None;
+            for (c, keys, description) in app.keymap.help() {
+                if context != Some(c) {
+                    if context.is_some() {
+                        text.push(text::Line::from(""));
+                    }
+                    text.push(text::Line::from(Span::styled(
+                        c.to_string(),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    )));
+                    context = Some(c);
+                }
+                text.push(text::Line::from(format!("<{}>: \t\t {}", keys, description)));
+            }
+            app.help_scroll = app.help_scroll.min(text.len().saturating_sub(1) as u16);
             let paragraph = Paragraph::new(text)
                 .style(text_style)
-                .block(info_block_popup);
+                .block(info_block_popup)
+                .scroll((app.help_scroll, 0));
             f.render_widget(Clear, info_layout_popup); //this clears out the background
             f.render_widget(paragraph, info_layout_popup);
         }
+        InputMode::Bulk => {
+            let bulk_block_popup = Block::default()
+                .title(format!("Bulk ({} selected)", app.targets().len()))
+                .borders(Borders::ALL)
+                .border_style(border_style)
+                .border_type(border_type);
+
+            let bulk_layout_popup = info_rect(25, 40, f.size());
+            let text: Vec<Line> = app
+                .keymap
+                .help()
+                .into_iter()
+                .filter(|(c, _, _)| *c == Context::Bulk)
+                .map(|(_, keys, description)| text::Line::from(format!("<{}>: \t\t {}", keys, description)))
+                .collect();
+            let paragraph = Paragraph::new(text)
+                .style(text_style)
+                .block(bulk_block_popup);
+            f.render_widget(Clear, bulk_layout_popup); //this clears out the background
+            f.render_widget(paragraph, bulk_layout_popup);
+        }
+        InputMode::Archive | InputMode::ArchiveSearch => {
+            let archive_layout_popup = info_rect(80, 80, f.size());
+            f.render_widget(Clear, archive_layout_popup); //this clears out the background
+            let searching = matches!(app.input_mode, InputMode::ArchiveSearch);
+            let archive_layout = Layout::new(
+                Direction::Vertical,
+                [Constraint::Length(if searching { 3 } else { 0 }), Constraint::Min(0)],
+            )
+            .split(archive_layout_popup);
+            if searching {
+                activate(
+                    &mut text_area[4],
+                    "Search archive".to_string(),
+                    border_type,
+                    border_style,
+                    text_style,
+                );
+                f.render_widget(text_area[4].widget(), archive_layout[0]);
+            }
+            let panes = Layout::new(
+                Direction::Horizontal,
+                [Constraint::Percentage(40), Constraint::Percentage(60)],
+            )
+            .split(archive_layout[1]);
+
+            let date_style = Style::new().fg(Color::from_str(conf.colors.task_date_color.as_str()).unwrap());
+            let header_style = Style::default().fg(Color::from_str(conf.colors.header_color.as_str()).unwrap()).bold();
+            let items: Vec<ListItem> = app
+                .archive_view
+                .iter()
+                .map(|i| {
+                    let t = &app.archive[*i];
+                    ListItem::new(Line::from(vec![
+                        Span::styled(format!("{} ", t.topic), get_topic_color(t.status.to_string(), conf)),
+                        Span::styled(t.name.clone(), text_style),
+                        Span::styled(format!(" {}", t.archived_date.clone().unwrap_or_default()), date_style),
+                    ]))
+                })
+                .collect();
+            let title = if app.archive_query.is_empty() {
+                format!(
+                    "Archive ({}) [{}: search, {}: restore]",
+                    app.archive.len(),
+                    app.keymap.keys(Context::Archive, Action::Search),
+                    app.keymap.keys(Context::Archive, Action::Restore)
+                )
+            } else {
+                format!("Archive ({}/{}) search: {}", app.archive_view.len(), app.archive.len(), app.archive_query)
+            };
+            let list = List::new(items)
+                .block(
+                    Block::default()
+                        .title(title)
+                        .borders(Borders::ALL)
+                        .border_style(border_style)
+                        .border_type(border_type),
+                )
+                .highlight_style(selected_style)
+                .highlight_symbol(conf.icons.cursor.as_str());
+            f.render_stateful_widget(list, panes[0], &mut app.archive_state);
+
+            let mut text = Text::raw("");
+            if let Some(task) = app.archive_selected().map(|i| &app.archive[i]) {
+                let mut lines = vec![
+                    Text::styled(format!("status:   {}", task.status), get_topic_color(task.status.to_string(), conf)),
+                    Text::styled(format!("topic:    {}", task.topic), date_style),
+                    Text::styled(format!("created:  {}", task.creation_date), date_style),
+                    Text::styled(format!("updated:  {}", task.status_change_date.clone().unwrap_or("-//-".to_string())), date_style),
+                    Text::styled(format!("duration: {}", task.duration.clone().unwrap_or("-//-".to_string())), date_style),
+                    Text::styled(format!("archived: {}", task.archived_date.clone().unwrap_or("-//-".to_string())), date_style),
+                    Text::raw(""),
+                    Text::styled("Name:", header_style),
+                    formatter.format(&task.name),
+                    Text::raw(""),
+                    Text::styled("Description:", header_style),
+                    formatter.format(&task.description),
+                ];
+                if !task.comments.is_empty() {
+                    lines.push(Text::raw(""));
+                    lines.push(Text::styled("Comments:", header_style));
+                    for comment in task.comments.iter() {
+                        lines.push(Text::styled(format!("#{} date: {}", comment.id, comment.date), date_style));
+                        lines.push(formatter.format(&comment.text));
+                    }
+                }
+                for line in lines {
+                    text.extend(line);
+                }
+            }
+            let paragraph = Paragraph::new(text)
+                .wrap(Wrap { trim: false })
+                .block(
+                    Block::default()
+                        .title("Archived task")
+                        .borders(Borders::ALL)
+                        .border_style(border_style)
+                        .border_type(border_type),
+                );
+            f.render_widget(paragraph, panes[1]);
+        }
+        InputMode::Sort => {
+            let sort_block_popup = Block::default()
+                .title("Sort")
+                .borders(Borders::ALL)
+                .border_style(border_style)
+                .border_type(border_type);
+
+            let sort_layout_popup = info_rect(30, 50, f.size());
+            let active = Style::new()
+                .fg(Color::from_str(conf.colors.icon_hold_color.as_str()).unwrap())
+                .bold();
+            let sort_keys = |i: usize| {
+                format!(
+                    "{}/{}",
+                    app.keymap.keys(Context::Sort, Action::SortBy(i, false)),
+                    app.keymap.keys(Context::Sort, Action::SortBy(i, true))
+                )
+            };
+            let mut text: Vec<Line> = SortKey::iterator()
+                .enumerate()
+                .map(|(i, key)| match &app.sort {
+                    Some((k, descending)) if *k == key => text::Line::from(Span::styled(
+                        format!("[x] <{}> {} {}", sort_keys(i), key, if *descending { "↓" } else { "↑" }),
+                        active,
+                    )),
+                    _ => text::Line::from(format!("[ ] <{}> {}", sort_keys(i), key)),
+                })
+                .collect();
+            let field_keys = app.keymap.keys(Context::Sort, Action::Field);
+            if !app.custom_fields.is_empty() {
+                text.push(match &app.sort {
+                    Some((SortKey::Field(name), descending)) => text::Line::from(Span::styled(
+                        format!("[x] <{}> field [{}] {}", field_keys, name, if *descending { "↓" } else { "↑" }),
+                        active,
+                    )),
+                    _ => text::Line::from(format!("[ ] <{}> by [Field]", field_keys)),
+                });
+            }
+            text.push(text::Line::from(format!(
+                "<{}>: \t keep current order",
+                app.keymap.keys(Context::Sort, Action::Clear)
+            )));
+            text.push(text::Line::from(""));
+            text.push(text::Line::from("<ascending/descending>"));
+            let paragraph = Paragraph::new(text)
+                .style(text_style)
+                .block(sort_block_popup);
+            f.render_widget(Clear, sort_layout_popup); //this clears out the background
+            f.render_widget(paragraph, sort_layout_popup);
+        }
+        InputMode::Stats => render_stats(f, app, conf, border_type, border_style),
+        InputMode::GoTo => {
+            let goto_layout_popup = info_rect(80, 70, f.size());
+            f.render_widget(Clear, goto_layout_popup); //this clears out the background
+            let goto_layout = Layout::new(
+                Direction::Vertical,
+                [Constraint::Length(3), Constraint::Min(0)],
+            )
+            .split(goto_layout_popup);
+            activate(
+                &mut text_area[4],
+                "Go to task".to_string(),
+                border_type,
+                border_style,
+                text_style,
+            );
+            f.render_widget(text_area[4].widget(), goto_layout[0]);
+            let panes = Layout::new(
+                Direction::Horizontal,
+                [Constraint::Percentage(50), Constraint::Percentage(50)],
+            )
+            .split(goto_layout[1]);
+
+            let date_style = Style::new().fg(Color::from_str(conf.colors.task_date_color.as_str()).unwrap());
+            let query = text_area[4].lines().concat();
+            let items: Vec<ListItem> = app
+                .goto_results
+                .iter()
+                .map(|i| {
+                    let t = &app.items[*i];
+                    let label = format!("{} {}", t.topic, t.name);
+                    let positions = fuzzy_match(&query, &label).map(|(_, p)| p).unwrap_or_default();
+                    let topic_len = t.topic.chars().count();
+                    let mut line = vec![Span::styled(
+                        format!("{} ", match_status(t.status.to_string(), conf)),
+                        get_icon_status_style(t.status.to_string(), conf),
+                    )];
+                    line.extend(label.chars().enumerate().map(|(n, c)| {
+                        let style = if positions.contains(&n) {
+                            match_style
+                        } else if n < topic_len {
+                            get_topic_color(t.status.to_string(), conf)
+                        } else {
+                            text_style
+                        };
+                        Span::styled(c.to_string(), style)
+                    }));
+                    if !t.display {
+                        line.push(Span::styled(" (hidden)", date_style));
+                    }
+                    ListItem::new(Line::from(line))
+                })
+                .collect();
+            let list = List::new(items)
+                .block(
+                    Block::default()
+                        .title(format!("Tasks ({}/{})", app.goto_results.len(), app.items.len()))
+                        .borders(Borders::ALL)
+                        .border_style(border_style)
+                        .border_type(border_type),
+                )
+                .highlight_style(selected_style)
+                .highlight_symbol(conf.icons.cursor.as_str());
+            f.render_stateful_widget(list, panes[0], &mut app.goto_state);
+
+            let mut text = Text::raw("");
+            if let Some(task) = app.goto_selected().map(|i| &app.items[i]) {
+                let mut lines = vec![
+                    Text::styled(format!("status:   {}", task.status), get_topic_color(task.status.to_string(), conf)),
+                    Text::styled(format!("topic:    {}", task.topic), date_style),
+                    Text::styled(format!("created:  {}", task.creation_date), date_style),
+                    Text::styled(format!("due:      {}", task.due_date.clone().unwrap_or("-//-".to_string())), date_style),
+                    Text::styled(format!("assignee: {}", task.assignee.clone().unwrap_or("-//-".to_string())), date_style),
+                ];
+                if !task.tags.is_empty() {
+                    lines.push(Text::styled(format!("tags:     #{}", task.tags.join(" #")), date_style));
+                }
+                lines.extend(vec![
+                    Text::raw(""),
+                    Text::styled("Name:", header_style.bold()),
+                    formatter.format(&task.name),
+                    Text::raw(""),
+                    Text::styled("Description:", header_style.bold()),
+                    formatter.format(&task.description),
+                ]);
+                for line in lines {
+                    text.extend(line);
+                }
+            }
+            let paragraph = Paragraph::new(text)
+                .wrap(Wrap { trim: false })
+                .block(
+                    Block::default()
+                        .title("Preview")
+                        .borders(Borders::ALL)
+                        .border_style(border_style)
+                        .border_type(border_type),
+                );
+            f.render_widget(paragraph, panes[1]);
+        }
+        InputMode::ViewPicker => {
+            let view_block_popup = Block::default()
+                .title("Views")
+                .borders(Borders::ALL)
+                .border_style(border_style)
+                .border_type(border_type);
+
+            let view_layout_popup = info_rect(40, 40, f.size());
+            f.render_widget(Clear, view_layout_popup); //this clears out the background
+            let items: Vec<ListItem> = app
+                .views
+                .iter()
+                .enumerate()
+                .map(|(i, v)| {
+                    let key = if i < 9 { format!("{} ", i + 1) } else { "  ".to_string() };
+                    ListItem::new(Line::from(vec![
+                        Span::styled(key, header_style),
+                        Span::styled(v.name.clone(), text_style),
+                        Span::styled(
+                            format!(" {}", v.filter),
+                            Style::new().fg(Color::from_str(conf.colors.task_date_color.as_str()).unwrap()),
+                        ),
+                    ]))
+                })
+                .collect();
+            let list = List::new(items)
+                .block(view_block_popup)
+                .highlight_style(selected_style)
+                .highlight_symbol(conf.icons.cursor.as_str());
+            f.render_stateful_widget(list, view_layout_popup, &mut app.view_state);
+        }
+        InputMode::TemplatePicker => {
+            let template_block_popup = Block::default()
+                .title("New from template")
+                .borders(Borders::ALL)
+                .border_style(border_style)
+                .border_type(border_type);
+
+            let template_layout_popup = info_rect(40, 40, f.size());
+            f.render_widget(Clear, template_layout_popup); //this clears out the background
+            if app.templates.is_empty() {
+                let text = match &app.template_error {
+                    Some(e) => e.clone(),
+                    None => "No templates found in ~/.config/todo/templates.yaml".to_string(),
+                };
+                let paragraph = Paragraph::new(text)
+                    .style(text_style)
+                    .wrap(Wrap { trim: false })
+                    .block(template_block_popup);
+                f.render_widget(paragraph, template_layout_popup);
+            } else {
+                let items: Vec<ListItem> = app
+                    .templates
+                    .iter()
+                    .map(|t| {
+                        ListItem::new(Line::from(vec![
+                            Span::styled(t.name.clone(), text_style),
+                            Span::styled(
+                                format!(" ({} sub tasks)", t.sub_tasks.len()),
+                                Style::new().fg(Color::from_str(conf.colors.task_date_color.as_str()).unwrap()),
+                            ),
+                        ]))
+                    })
+                    .collect();
+                let list = List::new(items)
+                    .block(template_block_popup)
+                    .highlight_style(selected_style)
+                    .highlight_symbol(conf.icons.cursor.as_str());
+                f.render_stateful_widget(list, template_layout_popup, &mut app.template_state);
+            }
+        }
         InputMode::FilterMode => {
             let filter_block_popup = Block::default()
                 .title("Filter")
@@ -313,13 +969,36 @@ pub fn ui(
                 .border_style(border_style)
                 .border_type(border_type);
 
-            let filter_layout_popup = info_rect(15, 15, f.size());
-            let text = self::filter_popup(app.filter, &conf);
+            let filter_layout_popup = info_rect(25, 30, f.size());
+            let mut text = self::filter_popup(app.filter, &app.keymap, conf);
+            let field_keys = app.keymap.keys(Context::Filter, Action::Field);
+            let query_keys = app.keymap.keys(Context::Filter, Action::Query);
+            if !app.custom_fields.is_empty() {
+                text.push(match &app.field_filter {
+                    Some((name, value)) => text::Line::from(Span::styled(
+                        format!("[x] <{}> by [{}={}]", field_keys, name, value),
+                        Style::new()
+                            .fg(Color::from_str(conf.colors.icon_hold_color.as_str()).unwrap())
+                            .bold(),
+                    )),
+                    None => text::Line::from(format!("[ ] <{}> by [Field]", field_keys)),
+                });
+            }
+            text.push(match &app.query {
+                Some((query, _)) => text::Line::from(Span::styled(
+                    format!("[x] <{}> query [{}]", query_keys, query),
+                    Style::new()
+                        .fg(Color::from_str(conf.colors.icon_hold_color.as_str()).unwrap())
+                        .bold(),
+                )),
+                None => text::Line::from(format!("[ ] <{}> by [Query]", query_keys)),
+            });
             let paragraph = Paragraph::new(text)
                 .style(text_style)
                 .block(filter_block_popup);
             f.render_widget(Clear, filter_layout_popup); //this clears out the background
             f.render_widget(paragraph, filter_layout_popup);
+            app.areas.filter_popup = filter_layout_popup;
         }
         _ => {
             let layout = centered_rect(50, 30, f.size());
@@ -393,6 +1072,29 @@ pub fn ui(
                         text_style,
                     );
                 }
+                InputArea::Fields => {
+                    inactivate(
+                        &mut text_area[0],
+                        "Topic".to_string(),
+                        border_type,
+                        border_style,
+                        text_style,
+                    );
+                    inactivate(
+                        &mut text_area[1],
+                        "Task".to_string(),
+                        border_type,
+                        border_style,
+                        text_style,
+                    );
+                    inactivate(
+                        &mut text_area[2],
+                        "Description".to_string(),
+                        border_type,
+                        border_style,
+                        text_style,
+                    );
+                }
                 _ => (),
             }
 
@@ -406,7 +1108,359 @@ pub fn ui(
             f.render_widget(widget_task, layout[1][0]);
             f.render_widget(Clear, layout[2][0]);
             f.render_widget(widget_desc, layout[2][0]);
+            let fields_area = render_fields(f, app, &mut text_area[5], layout[2][0], border_type, border_style, text_style);
+            app.areas.fields.push((layout[0][0], InputArea::Topic));
+            app.areas.fields.push((layout[1][0], InputArea::Task));
+            app.areas.fields.push((layout[2][0], InputArea::Description));
+            if let Some(area) = fields_area {
+                app.areas.fields.push((area, InputArea::Fields));
+            }
+        }
+    }
+}
+
+fn render_fields(
+    f: &mut Frame,
+    app: &App,
+    textarea: &mut TextArea<'_>,
+    above: Rect,
+    b_type: BorderType,
+    b_style: Style,
+    t_style: Style,
+) -> Option<Rect> {
+    if app.custom_fields.is_empty() {
+        return None;
+    }
+    let title = match &app.prompt_error {
+        Some(e) => format!("Fields ({})", e),
+        None => "Fields".to_string(),
+    };
+    if let InputArea::Fields = app.input_area {
+        activate(textarea, title, b_type, b_style, t_style);
+    } else {
+        inactivate(textarea, title, b_type, b_style, t_style);
+    }
+    let height = (app.custom_fields.len() as u16 + 2)
+        .min(f.size().height.saturating_sub(above.y + above.height));
+    let area = Rect::new(above.x, above.y + above.height, above.width, height);
+    f.render_widget(Clear, area);
+    f.render_widget(textarea.widget(), area);
+    Some(area)
+}
+
+fn render_stats(f: &mut Frame, app: &App, conf: &AppConfig, b_type: BorderType, b_style: Style) {
+    let area = info_rect(90, 90, f.size());
+    f.render_widget(Clear, area); //this clears out the background
+    let block = Block::default()
+        .title(format!("Statistics [{}: close]", app.keymap.keys(Context::Stats, Action::Close)))
+        .borders(Borders::ALL)
+        .border_type(b_type)
+        .border_style(b_style);
+    let rows = Layout::new(
+        Direction::Vertical,
+        [Constraint::Percentage(50), Constraint::Percentage(50)],
+    )
+    .split(block.inner(area));
+    f.render_widget(block, area);
+    let top = Layout::new(
+        Direction::Horizontal,
+        [Constraint::Percentage(55), Constraint::Percentage(45)],
+    )
+    .split(rows[0]);
+    let bottom = Layout::new(
+        Direction::Horizontal,
+        [Constraint::Percentage(40), Constraint::Percentage(35), Constraint::Percentage(25)],
+    )
+    .split(rows[1]);
+
+    let color = |c: &String| Color::from_str(c.as_str()).unwrap();
+    let created_style = Style::new().fg(color(&conf.colors.icon_new_color));
+    let completed_style = Style::new().fg(color(&conf.colors.icon_done_color));
+    let label_style = Style::new().fg(color(&conf.colors.task_date_color));
+    let axis_style = Style::new().fg(color(&conf.colors.border_color));
+    let titled = |title: Line<'static>| {
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_type(b_type)
+            .border_style(b_style)
+    };
+    let legend = |name: &str| {
+        Line::from(vec![
+            Span::raw(format!("{} ", name)),
+            Span::styled("■ created ", created_style),
+            Span::styled("■ completed", completed_style),
+        ])
+    };
+
+    let now = current_timestamp();
+    let tasks: Vec<&Task> = app.items.iter().chain(app.archive.iter()).collect();
+
+    // created vs completed, per day and per week
+    for (area, period, count, width, name) in [
+        (top[0], DAY, 14, 1, "Per day"),
+        (top[1], 7 * DAY, 8, 3, "Per week"),
+    ] {
+        let mut chart = BarChart::default()
+            .block(titled(legend(name)))
+            .bar_width(width)
+            .bar_gap(0)
+            .group_gap(1)
+            .label_style(label_style)
+            .value_style(Style::new().fg(Color::Black));
+        for (start, created, completed) in stats::created_completed(&tasks, now, period, count) {
+            let label = if period == DAY { to_day(start)[..2].to_string() } else { to_day(start)[..5].to_string() };
+            chart = chart.data(BarGroup::default().label(Line::from(label)).bars(&[
+                Bar::default().value(created).style(created_style),
+                Bar::default().value(completed).style(completed_style),
+            ]));
+        }
+        f.render_widget(chart, area);
+    }
+
+    // burndown of open tasks over the last 30 days
+    let points = stats::burndown(&tasks, now, 30);
+    let max = points.iter().map(|(_, y)| *y).fold(0.0, f64::max).max(1.0);
+    let dataset = Dataset::default()
+        .name("open")
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::new().fg(color(&conf.colors.icon_progress_color)))
+        .data(&points);
+    let chart = Chart::new(vec![dataset])
+        .block(titled(Line::from("Burndown (open tasks, 30 days)")))
+        .legend_position(None)
+        .x_axis(
+            Axis::default()
+                .style(axis_style)
+                .bounds([-29.0, 0.0])
+                .labels(vec![
+                    Span::styled("-29d", label_style),
+                    Span::styled("-14d", label_style),
+                    Span::styled("today", label_style),
+                ]),
+        )
+        .y_axis(
+            Axis::default()
+                .style(axis_style)
+                .bounds([0.0, max])
+                .labels(vec![
+                    Span::styled("0", label_style),
+                    Span::styled(format!("{}", max), label_style),
+                ]),
+        );
+    f.render_widget(chart, bottom[0]);
+
+    // average lead time per topic, in tenths of a day
+    let lead_style = Style::new().fg(color(&conf.colors.header_color));
+    let mut chart = BarChart::default()
+        .block(titled(Line::from("Avg lead time per topic")))
+        .direction(Direction::Horizontal)
+        .bar_width(1)
+        .bar_gap(0)
+        .label_style(label_style)
+        .value_style(lead_style.reversed());
+    let bars: Vec<Bar> = stats::lead_time_by_topic(&tasks)
+        .into_iter()
+        .map(|(topic, days)| {
+            Bar::default()
+                .label(Line::from(topic))
+                .value((days * 10.0).round() as u64)
+                .text_value(format!("{:.1}d", days))
+                .style(lead_style)
+        })
+        .collect();
+    chart = chart.data(BarGroup::default().bars(&bars));
+    f.render_widget(chart, bottom[1]);
+
+    // status distribution of the active list
+    let active: Vec<&Task> = app.items.iter().collect();
+    let bars: Vec<Bar> = stats::status_counts(&active)
+        .into_iter()
+        .map(|(status, count)| {
+            Bar::default()
+                .label(Line::from(match_status(status.to_string(), conf)))
+                .value(count)
+                .style(get_icon_status_style(status.to_string(), conf))
+        })
+        .collect();
+    let chart = BarChart::default()
+        .block(titled(Line::from("Status")))
+        .bar_width(4)
+        .bar_gap(1)
+        .label_style(label_style)
+        .value_style(Style::new().fg(Color::Black))
+        .data(BarGroup::default().bars(&bars));
+    f.render_widget(chart, bottom[2]);
+}
+
+fn render_calendar(
+    f: &mut Frame,
+    app: &App,
+    area: Rect,
+    conf: &AppConfig,
+    b_type: BorderType,
+    b_style: Style,
+) {
+    let layout = Layout::new(
+        Direction::Vertical,
+        [Constraint::Length(11), Constraint::Min(0)],
+    )
+    .split(area);
+    let now = current_timestamp();
+    let today = day_start(now);
+    let selected = app.calendar_date.unwrap_or(today);
+    let text_style = Style::new().fg(Color::from_str(conf.colors.task_text_color.as_str()).unwrap());
+    let header_style = Style::new().fg(Color::from_str(conf.colors.header_color.as_str()).unwrap());
+    let date_style = Style::new().fg(Color::from_str(conf.colors.task_date_color.as_str()).unwrap());
+    let overdue_style = Style::new().fg(Color::from_str(conf.colors.icon_hold_color.as_str()).unwrap());
+    let done_style = Style::new().fg(Color::from_str(conf.colors.icon_done_color.as_str()).unwrap());
+
+    let (title, days) = month_days(selected);
+    let first = days.iter().flatten().next().copied().unwrap_or(selected);
+    let mut lines = vec![Line::styled(" Mo Tu We Th Fr Sa Su", header_style.bold())];
+    for week in days.chunks(7) {
+        let mut line = vec![Span::raw(" ")];
+        for day in week {
+            let day = match day {
+                Some(d) => *d,
+                None => {
+                    line.push(Span::raw("   "));
+                    continue;
+                }
+            };
+            let tasks: Vec<&Task> = app.items.iter().filter(|t| t.on_day(day)).collect();
+            let mut style = if tasks.iter().any(|t| t.status != Status::Done) {
+                if day < today { overdue_style.bold() } else { date_style.bold() }
+            } else if !tasks.is_empty() {
+                done_style
+            } else {
+                text_style
+            };
+            if day == today {
+                style = style.underlined();
+            }
+            if day == selected {
+                style = style.reversed();
+            }
+            line.push(Span::styled(format!("{:>2}", (day - first) / DAY + 1), style));
+            line.push(Span::raw(" "));
+        }
+        lines.push(Line::from(line));
+    }
+    lines.push(Line::from(vec![
+        Span::styled(" ■ ", date_style),
+        Span::styled("due  ", text_style),
+        Span::styled("■ ", overdue_style),
+        Span::styled("overdue  ", text_style),
+        Span::styled("■ ", done_style),
+        Span::styled("done", text_style),
+    ]));
+    let calendar = Paragraph::new(lines).block(
+        Block::default()
+            .title(format!(" {} ", title))
+            .borders(Borders::ALL)
+            .border_type(b_type)
+            .border_style(b_style),
+    );
+    f.render_widget(calendar, layout[0]);
+
+    let mut lines: Vec<Line> = Vec::new();
+    for (section, tasks) in app.agenda(now) {
+        if tasks.is_empty() {
+            continue;
         }
+        let style = if section == "Overdue" { overdue_style } else { header_style };
+        lines.push(Line::styled(format!("{} ({})", section, tasks.len()), style.bold()));
+        for i in tasks {
+            let t = &app.items[i];
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!(" {} ", t.due_timestamp.map(to_day).unwrap_or_default()),
+                    date_style,
+                ),
+                Span::styled(format!("{} ", t.topic), get_topic_color(t.status.to_string(), conf)),
+                Span::styled(t.name.clone(), text_style),
+            ]));
+        }
+    }
+    if lines.is_empty() {
+        lines.push(Line::styled("No tasks with a due date", text_style));
+    }
+    let agenda = Paragraph::new(lines).block(
+        Block::default()
+            .title("Agenda")
+            .borders(Borders::ALL)
+            .border_type(b_type)
+            .border_style(b_style),
+    );
+    f.render_widget(agenda, layout[1]);
+}
+
+fn render_board(
+    f: &mut Frame,
+    app: &App,
+    area: Rect,
+    conf: &AppConfig,
+    b_type: BorderType,
+    b_style: Style,
+) {
+    let columns = Layout::new(
+        Direction::Horizontal,
+        BOARD_COLUMNS.iter().map(|_| Constraint::Ratio(1, BOARD_COLUMNS.len() as u32)),
+    )
+    .split(area);
+    let active = app.active_column();
+    let text_style = Style::new().fg(Color::from_str(conf.colors.task_text_color.as_str()).unwrap());
+    let date_style = Style::new().fg(Color::from_str(conf.colors.task_date_color.as_str()).unwrap());
+    let active_style = Style::new()
+        .fg(Color::from_str(conf.colors.header_color.as_str()).unwrap())
+        .bold();
+    for (column, status) in BOARD_COLUMNS.iter().enumerate() {
+        let cards = app.board_cards(column);
+        let items: Vec<ListItem> = cards
+            .iter()
+            .map(|i| {
+                let t = &app.items[*i];
+                let mut top = vec![Span::styled(t.topic.clone(), get_topic_color(t.status.to_string(), conf))];
+                if let Some(p) = t.priority {
+                    top.push(Span::styled(
+                        format!(" {}", priority_marker(p)),
+                        Style::new().fg(Color::from_str(conf.colors.icon_hold_color.as_str()).unwrap()).bold(),
+                    ));
+                }
+                if let Some(date) = &t.due_date {
+                    top.push(Span::styled(format!(" due {}", date), date_style));
+                }
+                let name = if t.is_sub_task {
+                    format!("{}{}", conf.icons.sub_task_end, t.name)
+                } else {
+                    t.name.clone()
+                };
+                ListItem::new(vec![Line::from(top), Line::from(Span::styled(name, text_style))])
+            })
+            .collect();
+        let title = format!(
+            " {}{} ({}) ",
+            match_status(status.to_string(), conf),
+            status,
+            cards.len()
+        );
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_type(b_type)
+            .border_style(if column == active { active_style } else { b_style });
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(get_selected_style())
+            .highlight_symbol(conf.icons.cursor.as_str());
+        let mut state = ListState::default().with_selected(
+            app.state
+                .selected()
+                .and_then(|i| cards.iter().position(|c| *c == i)),
+        );
+        f.render_stateful_widget(list, columns[column], &mut state);
     }
 }
 
@@ -568,18 +1622,32 @@ fn get_topic_icon_color(s: String, conf: &AppConfig) -> Style {
     }
 }
 
-fn filter_popup<'a>(f: Filter, conf: &AppConfig) -> Vec<Line<'a>> {
+/// Details pane title with the keys of the details mode.
+fn details_title(app: &App) -> String {
+    let keys = |action| app.keymap.keys(Context::Details, action);
+    format!(
+        "Details [{}: toggle, {}: open, {}: edit, {}: reply, {}: delete]",
+        keys(Action::ToggleCheck),
+        keys(Action::OpenLink),
+        keys(Action::EditComment),
+        keys(Action::Reply),
+        keys(Action::DeleteEntry)
+    )
+}
+
+fn filter_popup<'a>(f: Filter, keymap: &Keymap, conf: &AppConfig) -> Vec<Line<'a>> {
     Filter::iterator()
         .map(|x| {
+            let label = format!("<{}> by [{}]", keymap.keys(Context::Filter, Action::FilterBy(x)), x.label());
             if x == f {
                 text::Line::from(Span::styled(
-                    format!("[x] {}", x.to_string()),
+                    format!("[x] {}", label),
                     Style::new()
-                        .fg(Color::from_str(&conf.colors.icon_hold_color.as_str()).unwrap())
+                        .fg(Color::from_str(conf.colors.icon_hold_color.as_str()).unwrap())
                         .bold(),
                 ))
             } else {
-                text::Line::from(Span::styled(format!("[ ] {}", x.to_string()), Style::new()))
+                text::Line::from(Span::styled(format!("[ ] {}", label), Style::new()))
             }
         })
         .collect()
@@ -631,6 +1699,104 @@ fn topic_formatter<'a>(item_code: usize, task: &Task, conf: &AppConfig) -> Vec<S
     }
 }
 
+/// The bottom row: list name, a failed save, what the list shows, counts per
+/// status and, on the right, a transient message or the help key.
+fn render_status_bar(f: &mut Frame, app: &App, area: Rect, conf: &AppConfig, now: i64) {
+    let footer_style = Style::new().fg(Color::from_str(conf.colors.footer_color.as_str()).unwrap());
+    let error_style = Style::new().fg(Color::from_str(conf.colors.icon_hold_color.as_str()).unwrap());
+    let mut spans = vec![Span::styled(format!(" {} ", app.list_name()), footer_style.bold().reversed())];
+    // every change is saved right away, so only a failed save is worth showing
+    if app.save_error.is_some() {
+        spans.push(Span::styled(" ● not saved, :w to retry", error_style.bold()));
+    }
+
+    let shown = match app.view.and_then(|i| app.views.get(i)) {
+        Some(view) => format!("view: {}", view.name),
+        None => {
+            let mut parts = vec![app.filter.label().to_string()];
+            if let Some((query, _)) = &app.query {
+                parts.push(query.clone());
+            }
+            if let Some((name, value)) = &app.field_filter {
+                parts.push(format!("{}={}", name, value));
+            }
+            format!("filter: {}", parts.join(" "))
+        }
+    };
+    spans.push(Span::styled(format!("  {}", shown), footer_style));
+    for status in [Status::New, Status::InProgress, Status::Hold, Status::Done] {
+        let count = app.items.iter().filter(|t| t.display && t.status == status).count();
+        spans.push(Span::styled(
+            format!("  {}{}", match_status(status.to_string(), conf), count),
+            get_icon_status_style(status.to_string(), conf),
+        ));
+    }
+    if !app.search_query.is_empty() {
+        spans.push(Span::styled(
+            format!("  search: {} ({} hits)", app.search_query, app.search_hits().len()),
+            footer_style,
+        ));
+    }
+    if let Some((key, descending)) = &app.sort {
+        spans.push(Span::styled(
+            format!("  sort: {} {}", key, if *descending { "↓" } else { "↑" }),
+            footer_style,
+        ));
+    }
+
+    let (hint, hint_style) = match app.message.as_ref().filter(|m| !m.expired(now)) {
+        Some(message) if message.error => (message.text.clone(), error_style.bold()),
+        Some(message) => (message.text.clone(), footer_style.bold()),
+        None if !app.search_query.is_empty() => (
+            format!(
+                "{}/{}:next/previous hit, {}:clear ",
+                app.keymap.keys(Context::Search, Action::NextHit),
+                app.keymap.keys(Context::Search, Action::PreviousHit),
+                app.keymap.keys(Context::Normal, Action::Clear),
+            ),
+            footer_style,
+        ),
+        None => match app.keymap.keys(Context::Normal, Action::Help) {
+            keys if keys.is_empty() => (String::new(), footer_style),
+            keys => (format!("{}:keybidings ", keys), footer_style),
+        },
+    };
+    let layout = Layout::new(
+        Direction::Horizontal,
+        [Constraint::Min(0), Constraint::Length(hint.chars().count() as u16 + 1)],
+    )
+    .split(area);
+    f.render_widget(Paragraph::new(Line::from(spans)), layout[0]);
+    f.render_widget(
+        Paragraph::new(hint).style(hint_style).alignment(Alignment::Right),
+        layout[1],
+    );
+}
+
+fn group_header<'a>(app: &App, topic: &str, conf: &AppConfig) -> ListItem<'a> {
+    let header_style = Style::new().fg(Color::from_str(conf.colors.header_color.as_str()).unwrap());
+    let members: Vec<&Task> = (0..app.items.len())
+        .filter(|i| app.items[*i].display && app.topic_of(*i) == topic)
+        .map(|i| &app.items[i])
+        .collect();
+    let fold = if app.collapsed.iter().any(|t| t == topic) { "▸ " } else { "▾ " };
+    let mut line = vec![
+        Span::styled(fold, header_style),
+        Span::styled(topic.to_string(), header_style.bold()),
+        Span::styled(format!(" ({})", members.len()), header_style),
+    ];
+    for status in [Status::New, Status::InProgress, Status::Hold, Status::Done] {
+        let count = members.iter().filter(|t| t.status == status).count();
+        if count > 0 {
+            line.push(Span::styled(
+                format!("  {}{}", match_status(status.to_string(), conf), count),
+                get_icon_status_style(status.to_string(), conf),
+            ));
+        }
+    }
+    ListItem::new(Line::from(line))
+}
+
 fn info_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
@@ -651,6 +1817,65 @@ fn info_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         .split(popup_layout[1])[1]
 }
 
+/// Patches `style` onto every case-insensitive occurrence of `query`.
+fn highlight_line<'a>(line: Line<'a>, query: &str, style: Style) -> Line<'a> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return line;
+    }
+    let mut spans = Vec::new();
+    for span in line.spans {
+        let content = span.content.to_string();
+        let lower = content.to_lowercase();
+        // byte offsets are only shared when lowercasing keeps the length
+        if lower.len() != content.len() {
+            spans.push(span);
+            continue;
+        }
+        let mut start = 0;
+        for (at, _) in lower.match_indices(&query) {
+            if at > start {
+                spans.push(Span::styled(content[start..at].to_string(), span.style));
+            }
+            spans.push(Span::styled(
+                content[at..at + query.len()].to_string(),
+                span.style.patch(style),
+            ));
+            start = at + query.len();
+        }
+        if start < content.len() {
+            spans.push(Span::styled(content[start..].to_string(), span.style));
+        }
+    }
+    Line {
+        spans,
+        ..line
+    }
+}
+
+fn highlight_text<'a>(text: Text<'a>, query: &str, style: Style) -> Text<'a> {
+    Text::from(
+        text.lines
+            .into_iter()
+            .map(|l| highlight_line(l, query, style))
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn priority_marker(p: Priority) -> &'static str {
+    match p {
+        Priority::Low => "!",
+        Priority::Medium => "!!",
+        Priority::High => "!!!",
+    }
+}
+
+fn progress_bar(done: usize, total: usize) -> String {
+    let width = 5;
+    let filled = (done * width).checked_div(total).unwrap_or(0);
+    format!("{}{}", "▰".repeat(filled), "▱".repeat(width - filled))
+}
+
 fn display_child_list(c: &Vec<i64>, items: &Vec<Task>) -> usize {
     let mut counter = 0;
     for c_id in c {
@@ -663,3 +1888,45 @@ fn display_child_list(c: &Vec<i64>, items: &Vec<Task>) -> usize {
     }
     counter
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use std::fs;
+
+    fn status_bar(app: &App, conf: &AppConfig) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(120, 1)).unwrap();
+        terminal
+            .draw(|f| render_status_bar(f, app, f.size(), conf, current_timestamp()))
+            .unwrap();
+        terminal.backend().buffer().content.iter().map(|c| c.symbol()).collect()
+    }
+
+    #[test]
+    fn status_bar_shows_a_failed_save_until_it_is_retried() {
+        let home = std::env::temp_dir().join(format!("todo-ui-test-{}", std::process::id()));
+        fs::create_dir_all(&home).unwrap();
+        std::env::set_var("HOME", &home);
+        let conf = AppConfig::default();
+        let mut app = App::new(&conf);
+        app.write();
+        assert!(app.save_error.is_none());
+        assert!(!status_bar(&app, &conf).contains("not saved"));
+
+        // a directory in place of the list file makes every save fail
+        let list = home.join(".todo/todo.json");
+        fs::remove_file(&list).unwrap();
+        fs::create_dir(&list).unwrap();
+        app.write();
+        assert!(app.save_error.is_some());
+        assert!(status_bar(&app, &conf).contains("not saved"));
+
+        fs::remove_dir(&list).unwrap();
+        app.run_command("w");
+        assert!(app.save_error.is_none());
+        assert!(!status_bar(&app, &conf).contains("not saved"));
+        assert!(list.is_file());
+        fs::remove_dir_all(&home).unwrap();
+    }
+}